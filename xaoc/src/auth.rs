@@ -1,8 +1,8 @@
-use crate::{client, setup_dir};
+use crate::config::base_url;
+use crate::{client, setup_dir, url};
 use anyhow::{anyhow, bail, Context, Result};
 use regex::Regex;
 use reqwest::header::COOKIE;
-use reqwest::Url;
 use serde::{Deserialize, Serialize};
 use std::ffi::OsStr;
 use std::fs::File;
@@ -23,7 +23,7 @@ pub struct Token {
 }
 
 impl Token {
-    fn new(id: u64, name: &str, token: &str, current: bool) -> Self {
+    pub(crate) fn new(id: u64, name: &str, token: &str, current: bool) -> Self {
         Token {
             id,
             name: name.to_string(),
@@ -81,8 +81,8 @@ pub fn show() -> Result<()> {
 
 pub fn add(token: String) -> Result<()> {
     let base = setup_dir()?;
-    let base_url = "https://adventofcode.com/";
-    _add(&base, base_url, &token)
+    let base_url = base_url(&base)?;
+    _add(&base, &base_url, &token)
 }
 
 fn get_id_name(resp: &str) -> Result<(u64, String)> {
@@ -105,10 +105,8 @@ fn get_id_name(resp: &str) -> Result<(u64, String)> {
 fn _add(base: &Path, base_url: &str, token: &str) -> Result<()> {
     let mut auth = Auth::new(base)?;
     let client = client()?;
-    let mut url = Url::parse(base_url)?;
-    url.set_path("/2015/settings");
     let resp = client
-        .get(url)
+        .get(url(base_url, "/2015/settings")?)
         .header(COOKIE, format!("session={token}"))
         .send()?
         .error_for_status()
@@ -166,6 +164,29 @@ pub fn current_token() -> Result<Token> {
         .ok_or_else(|| anyhow!("no current token"))
 }
 
+/// The token of user id `user`, or else the one at index `user` in
+/// `xaoc auth list`.
+pub fn find_token(user: &str) -> Result<Token> {
+    let base = setup_dir()?;
+    _find_token(Auth::new(&base)?.tokens, user)
+}
+
+fn _find_token(tokens: Tokens, user: &str) -> Result<Token> {
+    let n: u64 = user.parse().with_context(|| format!("bad user {user}"))?;
+    let idx = match tokens.iter().position(|t| t.id == n) {
+        Some(idx) => idx,
+        None => n as usize,
+    };
+    tokens
+        .into_iter()
+        .nth(idx)
+        .ok_or_else(|| anyhow!("no user {user}, see xaoc auth list"))
+}
+
+pub fn tokens() -> Result<Tokens> {
+    Ok(Auth::new(&setup_dir()?)?.tokens)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -231,4 +252,17 @@ mod tests {
         }
         Ok(())
     }
+    #[test]
+    fn find_token() -> Result<()> {
+        let tokens = vec![
+            Token::new(123456, "Vasily", "F000", true),
+            Token::new(1, "John Kent", "F111", false),
+        ];
+        assert_eq!(_find_token(tokens.clone(), "1")?.token, "F111");
+        assert_eq!(_find_token(tokens.clone(), "0")?.token, "F000");
+        assert_eq!(_find_token(tokens.clone(), "123456")?.token, "F000");
+        assert!(_find_token(tokens.clone(), "2").is_err());
+        assert!(_find_token(tokens, "Vasily").is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,108 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+pub struct Stats {
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    fn new(mut times: Vec<Duration>) -> Self {
+        times.sort();
+        let runs = times.len();
+        let secs = times.iter().map(|t| t.as_secs_f64()).collect::<Vec<_>>();
+        let mean = secs.iter().sum::<f64>() / runs as f64;
+        let var = secs.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / runs as f64;
+        Stats {
+            runs,
+            min: times[0],
+            median: times[runs / 2],
+            mean: Duration::from_secs_f64(mean),
+            stddev: Duration::from_secs_f64(var.sqrt()),
+        }
+    }
+}
+
+fn us(d: Duration) -> String {
+    format!("{:.1}µs", d.as_secs_f64() * 1e6)
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "min {} median {} mean {} ±{} over {} runs",
+            us(self.min),
+            us(self.median),
+            us(self.mean),
+            us(self.stddev),
+            self.runs
+        )
+    }
+}
+
+/// Times `f` `runs` times, or as many times as fit in `budget` if `runs`
+/// isn't given. The caller is expected to have run `f` once already, which
+/// serves as the warmup.
+pub fn bench(f: impl Fn(), runs: Option<usize>, budget: Duration) -> Stats {
+    let mut times = vec![];
+    let start = Instant::now();
+    loop {
+        let t = Instant::now();
+        f();
+        times.push(t.elapsed());
+        let done = match runs {
+            Some(runs) => times.len() >= runs,
+            None => start.elapsed() >= budget,
+        };
+        if done {
+            break;
+        }
+    }
+    Stats::new(times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn stats() {
+        let ms = Duration::from_millis;
+        let stats = Stats::new(vec![ms(4), ms(1), ms(3), ms(2)]);
+        assert_eq!(stats.runs, 4);
+        assert_eq!(stats.min, ms(1));
+        // the upper of the two middle runs
+        assert_eq!(stats.median, ms(3));
+        assert!((stats.mean.as_secs_f64() - 0.0025).abs() < 1e-9);
+        // population, not sample, deviation
+        assert!((stats.stddev.as_secs_f64() - 1.25e-6f64.sqrt()).abs() < 1e-9);
+        assert_eq!(Stats::new(vec![ms(5), ms(1), ms(3)]).median, ms(3));
+        assert_eq!(
+            Stats::new(vec![ms(2)]).to_string(),
+            "min 2000.0µs median 2000.0µs mean 2000.0µs ±0.0µs over 1 runs"
+        );
+    }
+
+    #[test]
+    fn bench() {
+        let calls = Cell::new(0);
+        let stats = super::bench(|| calls.set(calls.get() + 1), Some(5), Duration::ZERO);
+        assert_eq!((stats.runs, calls.get()), (5, 5));
+
+        let budget = Duration::from_millis(30);
+        let start = Instant::now();
+        let stats = super::bench(
+            || std::thread::sleep(Duration::from_millis(10)),
+            None,
+            budget,
+        );
+        assert!(start.elapsed() >= budget);
+        // sleeps don't end early, so the third run always fills the budget
+        assert!((1..=3).contains(&stats.runs), "{} runs", stats.runs);
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::{bail, Context, Result};
 use clap::{Args, Parser, Subcommand};
 use itertools::Itertools;
+use std::ops::RangeInclusive;
 use std::os::unix::net::UnixListener;
 use std::path::Path;
+use std::time::Duration;
 use uds::UnixListenerExt;
-use xaoc::{auth, puzzle, runner};
+use xaoc::{auth, history, jobs, puzzle, runner, status};
 
 #[derive(Parser, Debug)]
 struct Cli {
@@ -19,29 +21,141 @@ struct Cli {
 enum Commands {
     Prepare {
         day: u16,
+        /// Download the puzzle page again, e.g. to get part 2
+        #[clap(long)]
+        refresh: bool,
+        /// Count down to the unlock and fetch right after it
+        #[clap(long)]
+        wait: bool,
     },
     Auth(Auth),
     Submit {
         day: u16,
         part: u16,
         answer: String,
+        #[clap(long)]
+        wait: bool,
     },
     Map {
+        day: u16,
+        part: u16,
         answer: String,
     },
-    Unmap,
+    Unmap {
+        day: u16,
+        part: u16,
+    },
     Run {
-        #[clap(long)]
-        debug: bool,
+        #[clap(long, value_enum, default_value_t)]
+        format: runner::Format,
+        #[command(flatten)]
+        days: DaysArgs,
     },
+    /// Run every day on the cached input of every account and check its answers
+    Verify(DaysArgs),
     FixDeps,
     FixUse,
+    /// Show timing trends from the run history
+    Report {
+        #[clap(long)]
+        year: Option<u16>,
+        /// Percent slowdown against the best run that counts as a regression
+        #[clap(long, default_value_t = 20.0)]
+        threshold: f64,
+    },
     SyncAnswers {
         #[clap(long)]
         force: bool,
+        /// Every event so far instead of the current crate's year
+        #[clap(long)]
+        all_years: bool,
+        /// Every token instead of the current one
+        #[clap(long)]
+        all_users: bool,
+    },
+    /// Show stars, solutions, cached inputs and last runs per day
+    Status {
+        #[command(flatten)]
+        years: YearsArgs,
+        /// Fetch missing answers from the site first
+        #[clap(long)]
+        sync: bool,
+    },
+    /// Print the cached puzzle text of a day
+    Show {
+        day: u16,
+        #[clap(long)]
+        part: Option<u16>,
+        /// Plain Markdown instead of colored text
+        #[clap(long)]
+        markdown: bool,
+    },
+    /// List the sample blocks of a day for --sample
+    Samples {
+        day: u16,
     },
 }
 
+/// Which years to cover, none meaning the current crate's.
+#[derive(Args, Debug)]
+struct YearsArgs {
+    #[clap(long, conflicts_with = "years")]
+    year: Option<u16>,
+    /// Inclusive range like 2015..2025
+    #[clap(long, value_parser = runner::parse_years)]
+    years: Option<RangeInclusive<u16>>,
+}
+
+impl YearsArgs {
+    fn years(&self) -> Vec<u16> {
+        match (self.year, &self.years) {
+            (Some(year), _) => vec![year],
+            (None, Some(years)) => years.clone().collect(),
+            (None, None) => vec![],
+        }
+    }
+}
+
+/// Which day binaries to run and how.
+#[derive(Args, Debug)]
+struct DaysArgs {
+    #[clap(long)]
+    debug: bool,
+    #[command(flatten)]
+    years: YearsArgs,
+    #[clap(long)]
+    day: Option<u16>,
+    #[clap(long)]
+    part: Option<u16>,
+    /// Binaries to run at once. With more than one their timings compete for
+    /// the CPU, so they aren't recorded in the history
+    #[clap(short, long, default_value_t = 1)]
+    jobs: usize,
+    /// Seconds before a binary is killed, 0 for no limit
+    #[clap(long, default_value_t = 300.0)]
+    timeout: f64,
+    /// Address space limit per binary in MiB
+    #[clap(long)]
+    memory: Option<u64>,
+}
+
+impl DaysArgs {
+    fn options(self, format: runner::Format) -> runner::RunAllOptions {
+        runner::RunAllOptions {
+            debug: self.debug,
+            format,
+            years: self.years.years(),
+            day: self.day,
+            part: self.part,
+            jobs: self.jobs,
+            limits: jobs::Limits {
+                timeout: (self.timeout > 0.0).then(|| Duration::from_secs_f64(self.timeout)),
+                memory: self.memory.map(|mib| mib << 20),
+            },
+        }
+    }
+}
+
 #[derive(Args, Debug)]
 struct Auth {
     #[command(subcommand)]
@@ -69,7 +183,7 @@ fn main() -> Result<()> {
     }
 
     match cli.command {
-        Commands::Prepare { day } => runner::prepare(xaoc::Day(day))?,
+        Commands::Prepare { day, refresh, wait } => runner::prepare(xaoc::Day(day), refresh, wait)?,
         Commands::Auth(auth) => match auth.command {
             AuthCommands::Show => auth::show()?,
             AuthCommands::Add { token } => {
@@ -85,17 +199,33 @@ fn main() -> Result<()> {
                 auth::show()?;
             }
         },
-        Commands::Submit { day, part, answer } => {
-            puzzle::submit(day, part, &answer)?;
-        }
-        Commands::Run { debug } => {
-            runner::run_all(debug)?;
+        Commands::Submit {
+            day,
+            part,
+            answer,
+            wait,
+        } => {
+            puzzle::submit(day, part, &answer, wait)?;
         }
-        Commands::Map { answer } => puzzle::map(&answer)?,
-        Commands::Unmap => puzzle::unmap()?,
+        Commands::Run { format, days } => runner::run_all(&days.options(format))?,
+        Commands::Verify(days) => runner::verify(&days.options(runner::Format::Text))?,
+        Commands::Map { day, part, answer } => puzzle::map(day, part, &answer)?,
+        Commands::Unmap { day, part } => puzzle::unmap(day, part)?,
         Commands::FixUse => fix_use()?,
         Commands::FixDeps => fix_deps()?,
-        Commands::SyncAnswers { force } => runner::sync_answers(force)?,
+        Commands::Report { year, threshold } => history::report(year, threshold)?,
+        Commands::SyncAnswers {
+            force,
+            all_years,
+            all_users,
+        } => runner::sync_answers(force, all_years, all_users)?,
+        Commands::Samples { day } => puzzle::samples(day)?,
+        Commands::Status { years, sync } => status::status(&years.years(), sync)?,
+        Commands::Show {
+            day,
+            part,
+            markdown,
+        } => puzzle::show(day, part, markdown)?,
     }
     Ok(())
 }
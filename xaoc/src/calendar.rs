@@ -0,0 +1,179 @@
+//! Which days each event has and when they unlock.
+
+use crate::config::Config;
+use crate::{Day, Part, Year};
+use anyhow::{bail, Result};
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const FIRST_YEAR: u16 = 2015;
+/// Events ran 25 days until this year and 12 from then on.
+const SHORT_FROM: u16 = 2025;
+/// Puzzles unlock at midnight US Eastern, which is UTC-5 in December.
+const UNLOCK_HOUR_UTC: u64 = 5;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Event {
+    pub year: u16,
+    /// Number of days, the last of which has a single puzzle.
+    pub days: u16,
+}
+
+impl Event {
+    /// The event of `year`, which takes its length from `days` in the config
+    /// if listed there.
+    pub fn new(year: Year, config: &Config) -> Result<Self> {
+        if year.0 < FIRST_YEAR {
+            bail!("bad year {year}");
+        }
+        let days = match config.days.get(&year.to_string()) {
+            Some(&days) => days,
+            None if year.0 < SHORT_FROM => 25,
+            None => 12,
+        };
+        Ok(Event { year: year.0, days })
+    }
+
+    /// Like [`Event::new`], with the config in `root`.
+    pub fn load(root: &Path, year: Year) -> Result<Self> {
+        Self::new(year, &Config::new(root)?)
+    }
+
+    pub fn days(&self) -> impl Iterator<Item = Day> {
+        (1..=self.days).map(Day)
+    }
+
+    pub fn check_day(&self, day: Day) -> Result<()> {
+        if !(1..=self.days).contains(&day.0) {
+            bail!("bad day {day}, {} has {} days", self.year, self.days);
+        }
+        Ok(())
+    }
+
+    /// Whether `part` of `day` is a puzzle. Part 2 of the last day is a star
+    /// given for finishing all the others.
+    pub fn is_puzzle(&self, day: Day, part: Part) -> bool {
+        !(day.0 == self.days && matches!(part, Part::Two))
+    }
+
+    pub fn unlock(&self, day: Day) -> SystemTime {
+        let days = days_from_civil(self.year as u64, 12, day.0 as u64);
+        UNIX_EPOCH + Duration::from_secs(days * 86400 + UNLOCK_HOUR_UTC * 3600)
+    }
+}
+
+/// Years whose event has started by `now`.
+pub fn started_years(now: SystemTime) -> Vec<Year> {
+    (FIRST_YEAR..)
+        .map(|year| Event { year, days: 1 })
+        .take_while(|event| event.unlock(Day(1)) <= now)
+        .map(|event| Year(event.year))
+        .collect()
+}
+
+/// Where the time comes from, so waiting for an unlock can be tested.
+pub trait Clock {
+    fn now(&self) -> SystemTime;
+    fn sleep(&self, d: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+
+    fn sleep(&self, d: Duration) {
+        std::thread::sleep(d);
+    }
+}
+
+fn countdown(left: Duration) -> String {
+    let secs = left.as_secs();
+    format!("{}h {:02}m {:02}s", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+/// Sleeps until `at`, counting down on one line.
+pub fn wait_until(at: SystemTime, clock: &dyn Clock) {
+    let mut waited = false;
+    while let Ok(left) = at.duration_since(clock.now()) {
+        if left.is_zero() {
+            break;
+        }
+        // round up so the last second shows as 1s, not 0s
+        print!(
+            "\runlocks in {} ",
+            countdown(left + Duration::from_millis(999))
+        );
+        let _ = std::io::stdout().flush();
+        clock.sleep(left.min(Duration::from_secs(1)));
+        waited = true;
+    }
+    if waited {
+        println!();
+    }
+}
+
+/// Days from 1970-01-01 to a later date.
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    // years start in March so the leap day comes last
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn days() -> Result<()> {
+        let config = Config::default();
+        assert_eq!(Event::new(Year(2015), &config)?.days, 25);
+        assert_eq!(Event::new(Year(2024), &config)?.days, 25);
+        assert_eq!(Event::new(Year(2025), &config)?.days, 12);
+        assert_eq!(Event::new(Year(2031), &config)?.days, 12);
+        assert!(Event::new(Year(2014), &config).is_err());
+        let config: Config = toml::from_str("[days]\n2031 = 20")?;
+        let event = Event::new(Year(2031), &config)?;
+        assert_eq!(event.days, 20);
+        assert!(event.check_day(Day(20)).is_ok());
+        assert!(event.check_day(Day(21)).is_err());
+        assert!(event.check_day(Day(0)).is_err());
+        assert!(event.is_puzzle(Day(19), Part::Two));
+        assert!(event.is_puzzle(Day(20), Part::One));
+        assert!(!event.is_puzzle(Day(20), Part::Two));
+        Ok(())
+    }
+
+    #[test]
+    fn countdown() {
+        assert_eq!(super::countdown(Duration::from_secs(3725)), "1h 02m 05s");
+        assert_eq!(super::countdown(Duration::from_secs(59)), "0h 00m 59s");
+    }
+
+    #[test]
+    fn unlock() -> Result<()> {
+        let event = Event::new(Year(2015), &Config::default())?;
+        // 2015-12-01T05:00:00Z
+        assert_eq!(
+            event.unlock(Day(1)),
+            UNIX_EPOCH + Duration::from_secs(1448946000)
+        );
+        let event = Event::new(Year(2024), &Config::default())?;
+        // 2024-12-25T05:00:00Z
+        assert_eq!(
+            event.unlock(Day(25)),
+            UNIX_EPOCH + Duration::from_secs(1735102800)
+        );
+        let years = started_years(event.unlock(Day(1)));
+        assert_eq!(years.first().map(|y| y.0), Some(2015));
+        assert_eq!(years.last().map(|y| y.0), Some(2024));
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com/";
+pub const BASE_URL_ENV: &str = "XAOC_BASE_URL";
+
+/// Optional settings from `config.toml` in the config dir.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default)]
+pub struct Config {
+    pub base_url: Option<String>,
+    /// Number of days of an event by year, for events the calendar gets wrong.
+    pub days: BTreeMap<String, u16>,
+}
+
+impl Config {
+    pub fn new(root: &Path) -> Result<Self> {
+        let path = root.join("config.toml");
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let config = std::fs::read_to_string(&path)?;
+        let config = toml::from_str(&config).with_context(|| format!("parse {path:?}"))?;
+        Ok(config)
+    }
+}
+
+/// Server to talk to: `$XAOC_BASE_URL`, then `base_url` from `config.toml`,
+/// then the real site.
+pub fn base_url(root: &Path) -> Result<String> {
+    if let Ok(url) = std::env::var(BASE_URL_ENV) {
+        return Ok(url);
+    }
+    Ok(Config::new(root)?
+        .base_url
+        .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()))
+}
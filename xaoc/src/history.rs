@@ -0,0 +1,287 @@
+use crate::setup_dir;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::Command;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Good,
+    Bad,
+    Unknown,
+    Multiline,
+    /// The part returned an error.
+    Error,
+    Panic,
+}
+
+impl Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Status::Good => "good",
+            Status::Bad => "bad",
+            Status::Unknown => "unknown",
+            Status::Multiline => "multiline",
+            Status::Error => "error",
+            Status::Panic => "panic",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl Status {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Status::Good => "✅",
+            Status::Bad => "❌",
+            Status::Unknown | Status::Multiline => "❓",
+            Status::Error => "🚫",
+            Status::Panic => "💥",
+        }
+    }
+}
+
+/// One timed part, as appended to `history.jsonl` in the config dir.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Record {
+    /// Unix seconds.
+    pub at: u64,
+    pub year: u16,
+    pub day: u16,
+    pub part: u16,
+    pub user: u64,
+    pub micros: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_micros: Option<u64>,
+    pub status: Status,
+    pub commit: Option<String>,
+    pub profile: String,
+}
+
+impl Record {
+    pub fn new(
+        year: u16,
+        day: u16,
+        part: u16,
+        user: u64,
+        elapsed: Duration,
+        status: Status,
+    ) -> Self {
+        Record {
+            at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            year,
+            day,
+            part,
+            user,
+            micros: elapsed.as_micros() as u64,
+            parse_micros: None,
+            status,
+            commit: git_commit(),
+            profile: profile().to_string(),
+        }
+    }
+}
+
+pub fn profile() -> &'static str {
+    if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    }
+}
+
+/// Set by `xaoc run` so its day binaries don't each ask git.
+pub(crate) const COMMIT_VAR: &str = "XAOC_COMMIT";
+
+/// Set by `xaoc run -j <n>` so day binaries running side by side don't
+/// record their timings.
+pub(crate) const NO_HISTORY_VAR: &str = "XAOC_NO_HISTORY";
+
+/// Short hash of the checked out commit, with `+` appended if the tree is
+/// dirty, looked up once per process.
+pub(crate) fn git_commit() -> Option<String> {
+    static COMMIT: OnceLock<Option<String>> = OnceLock::new();
+    COMMIT
+        .get_or_init(|| match std::env::var(COMMIT_VAR) {
+            Ok(commit) => Some(commit).filter(|c| !c.is_empty()),
+            Err(_) => read_git_commit(),
+        })
+        .clone()
+}
+
+fn read_git_commit() -> Option<String> {
+    let out = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()?;
+    if !out.status.success() {
+        return None;
+    }
+    let mut commit = String::from_utf8(out.stdout).ok()?.trim().to_string();
+    let clean = Command::new("git")
+        .args(["diff", "--quiet", "HEAD"])
+        .status()
+        .ok()?
+        .success();
+    if !clean {
+        commit.push('+');
+    }
+    Some(commit)
+}
+
+pub fn append(root: &Path, record: &Record) -> Result<()> {
+    let mut f = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(root.join("history.jsonl"))?;
+    // one write per record so concurrent runs don't interleave
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    f.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+pub fn load(root: &Path) -> Result<Vec<Record>> {
+    let path = root.join("history.jsonl");
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let mut records = vec![];
+    for (i, line) in BufReader::new(File::open(&path)?).lines().enumerate() {
+        let record = serde_json::from_str(&line?)
+            .with_context(|| format!("{}:{}", path.display(), i + 1))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn ms(micros: u64) -> String {
+    format!("{:.3}ms", micros as f64 / 1000.0)
+}
+
+/// Year, day, part, profile and user.
+type PartKey<'a> = (u16, u16, u16, &'a str, u64);
+
+/// The records of `year`, or of every year, grouped by part, profile and user,
+/// oldest first.
+fn group(records: &[Record], year: Option<u16>) -> BTreeMap<PartKey<'_>, Vec<&Record>> {
+    let mut parts = BTreeMap::<_, Vec<&Record>>::new();
+    for r in records.iter().filter(|r| year.is_none_or(|y| r.year == y)) {
+        parts
+            .entry((r.year, r.day, r.part, r.profile.as_str(), r.user))
+            .or_default()
+            .push(r);
+    }
+    parts
+}
+
+/// Percent by which the last of `runs` is slower than the best.
+fn slowdown(runs: &[&Record]) -> f64 {
+    let best = runs.iter().map(|r| r.micros).min().unwrap_or(0);
+    let last = runs.last().map_or(0, |r| r.micros);
+    (last as f64 / best.max(1) as f64 - 1.0) * 100.0
+}
+
+/// Prints the recent timings of every part and flags the ones whose last
+/// run is more than `threshold` percent slower than their best run.
+pub fn report(year: Option<u16>, threshold: f64) -> Result<()> {
+    let records = load(&setup_dir()?)?;
+    let users = records.iter().map(|r| r.user).unique().count();
+    let parts = group(&records, year);
+    let mut regressed = 0;
+    for ((year, day, part, profile, user), runs) in &parts {
+        let best = runs.iter().min_by_key(|r| r.micros).unwrap();
+        let last = runs.last().unwrap();
+        let trend = runs
+            .iter()
+            .rev()
+            .take(5)
+            .rev()
+            .map(|r| ms(r.micros))
+            .join(" → ");
+        let user = if users > 1 {
+            format!(" user {user}")
+        } else {
+            String::new()
+        };
+        let mut line = format!(
+            "{year} day {day:>2} part {part} {profile}{user} {} best {} ({}) last {} ({}) [{trend}]",
+            last.status.symbol(),
+            ms(best.micros),
+            best.commit.as_deref().unwrap_or("?"),
+            ms(last.micros),
+            last.commit.as_deref().unwrap_or("?"),
+        );
+        let slower = slowdown(runs);
+        if slower > threshold {
+            regressed += 1;
+            line = format!("{line} {}", format!("+{slower:.0}%").red().bold());
+        }
+        println!("{line}");
+    }
+    println!(
+        "{} parts, {regressed} regressed by more than {threshold}%",
+        parts.len()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    #[test]
+    fn append_load_group() -> Result<()> {
+        let root = TempDir::new()?;
+        assert!(load(&root)?.is_empty());
+        let ms = Duration::from_millis;
+        let runs = [
+            (2015, 1, 1, 1, ms(10), Status::Good),
+            (2015, 1, 1, 1, ms(8), Status::Good),
+            (2015, 1, 1, 1, ms(12), Status::Bad),
+            (2015, 1, 1, 2, ms(30), Status::Good),
+            (2015, 1, 2, 1, ms(5), Status::Good),
+            (2016, 1, 1, 1, ms(5), Status::Panic),
+        ];
+        for (year, day, part, user, elapsed, status) in runs {
+            append(&root, &Record::new(year, day, part, user, elapsed, status))?;
+        }
+        let records = load(&root)?;
+        assert_eq!(records.len(), 6);
+        assert_eq!(records[2].status, Status::Bad);
+        assert_eq!(records[2].micros, 12000);
+
+        let parts = group(&records, Some(2015));
+        let profile = profile();
+        assert_eq!(
+            parts.keys().copied().collect::<Vec<_>>(),
+            [
+                (2015, 1, 1, profile, 1),
+                (2015, 1, 1, profile, 2),
+                (2015, 1, 2, profile, 1),
+            ]
+        );
+        // the last run against the best, not the first
+        let runs = &parts[&(2015, 1, 1, profile, 1)];
+        assert_eq!(
+            runs.iter().map(|r| r.micros).collect::<Vec<_>>(),
+            [10000, 8000, 12000]
+        );
+        assert_eq!(slowdown(runs), 50.0);
+        assert_eq!(slowdown(&parts[&(2015, 1, 1, profile, 2)]), 0.0);
+        assert_eq!(group(&records, None).len(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,196 @@
+//! Running day binaries side by side, each with its own limits.
+
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::io::Read;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub struct Limits {
+    pub timeout: Option<Duration>,
+    /// Address space limit in bytes.
+    pub memory: Option<u64>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Ok,
+    Failed(i32),
+    Crashed(i32),
+    TimedOut,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Outcome::Ok => write!(f, "ok"),
+            Outcome::Failed(101) => write!(f, "panicked"),
+            Outcome::Failed(code) => write!(f, "exited with {code}"),
+            Outcome::Crashed(signal) => write!(f, "killed by signal {signal}"),
+            Outcome::TimedOut => write!(f, "timed out"),
+        }
+    }
+}
+
+pub struct Finished<T> {
+    pub job: T,
+    pub outcome: Outcome,
+    pub stdout: String,
+    pub stderr: String,
+    pub elapsed: Duration,
+}
+
+fn read_all(mut r: impl Read + Send + 'static) -> std::thread::JoinHandle<String> {
+    std::thread::spawn(move || {
+        let mut buf = vec![];
+        let _ = r.read_to_end(&mut buf);
+        String::from_utf8_lossy(&buf).into_owned()
+    })
+}
+
+fn wait(child: &mut Child, timeout: Option<Duration>) -> Result<Outcome> {
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if timeout.is_some_and(|t| start.elapsed() > t) {
+            // the whole group, or grandchildren holding the pipes keep them open
+            // SAFETY: kill only sends a signal
+            if unsafe { libc::kill(-(child.id() as i32), libc::SIGKILL) } != 0 {
+                return Err(std::io::Error::last_os_error().into());
+            }
+            child.wait()?;
+            return Ok(Outcome::TimedOut);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    Ok(match (status.code(), status.signal()) {
+        (Some(0), _) => Outcome::Ok,
+        (Some(code), _) => Outcome::Failed(code),
+        (None, Some(signal)) => Outcome::Crashed(signal),
+        (None, None) => unreachable!(),
+    })
+}
+
+fn run_one(cmd: &mut Command, limits: &Limits) -> Result<(Outcome, String, String)> {
+    if let Some(memory) = limits.memory {
+        // SAFETY: setrlimit is async-signal-safe and nothing else runs between fork and exec
+        unsafe {
+            cmd.pre_exec(move || {
+                let limit = libc::rlimit {
+                    rlim_cur: memory,
+                    rlim_max: memory,
+                };
+                if libc::setrlimit(libc::RLIMIT_AS, &limit) != 0 {
+                    return Err(std::io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    let mut child = cmd
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // drain both pipes so a chatty child can't block on a full buffer
+    let stdout = read_all(child.stdout.take().unwrap());
+    let stderr = read_all(child.stderr.take().unwrap());
+    let outcome = wait(&mut child, limits.timeout)?;
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok((outcome, stdout, stderr))
+}
+
+/// Runs the command of every job on `workers` threads and hands each result
+/// to `done` on the calling thread as soon as it finishes.
+pub fn run<T: Send>(
+    jobs: Vec<(T, Command)>,
+    workers: usize,
+    limits: &Limits,
+    mut done: impl FnMut(Finished<T>) -> Result<()>,
+) -> Result<()> {
+    let queue = Mutex::new(jobs.into_iter().collect::<VecDeque<_>>());
+    let (tx, rx) = mpsc::channel();
+    std::thread::scope(|s| {
+        for _ in 0..workers.max(1) {
+            let tx = tx.clone();
+            let queue = &queue;
+            s.spawn(move || loop {
+                let Some((job, mut cmd)) = queue.lock().unwrap().pop_front() else {
+                    break;
+                };
+                let start = Instant::now();
+                let res = run_one(&mut cmd, limits).map(|(outcome, stdout, stderr)| Finished {
+                    job,
+                    outcome,
+                    stdout,
+                    stderr,
+                    elapsed: start.elapsed(),
+                });
+                if tx.send(res).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+        for res in rx {
+            done(res?)?;
+        }
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Command {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", script]);
+        cmd
+    }
+
+    #[test]
+    fn outcomes() -> Result<()> {
+        let jobs = vec![
+            ("ok", sh("echo out; echo err >&2")),
+            ("failed", sh("exit 3")),
+            ("crashed", sh("kill -SEGV $$")),
+            // the sleep outlives its shell unless the group is killed
+            ("timed out", sh("sleep 5; true")),
+        ];
+        let limits = Limits {
+            timeout: Some(Duration::from_millis(500)),
+            memory: None,
+        };
+        let start = Instant::now();
+        let mut outcomes = vec![];
+        run(jobs, 4, &limits, |finished| {
+            if finished.job == "ok" {
+                assert_eq!(finished.stdout, "out\n");
+                assert_eq!(finished.stderr, "err\n");
+            }
+            outcomes.push((finished.job, finished.outcome));
+            Ok(())
+        })?;
+        assert!(start.elapsed() < Duration::from_secs(3));
+        outcomes.sort_by_key(|(job, _)| *job);
+        assert_eq!(
+            outcomes,
+            [
+                ("crashed", Outcome::Crashed(11)),
+                ("failed", Outcome::Failed(3)),
+                ("ok", Outcome::Ok),
+                ("timed out", Outcome::TimedOut),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,11 +1,21 @@
 pub mod auth;
+pub mod bench;
+pub mod calendar;
+pub mod config;
+pub mod history;
+pub mod jobs;
+pub mod ocr;
 pub mod puzzle;
 pub mod runner;
+pub mod status;
+#[cfg(test)]
+mod testing;
 
 use anyhow::{bail, Context, Result};
 use md5::{Digest, Md5};
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, USER_AGENT};
+use reqwest::Url;
 use std::fmt::Display;
 use std::path::PathBuf;
 
@@ -24,6 +34,16 @@ pub fn client() -> Result<Client> {
     Ok(client)
 }
 
+pub fn url(base_url: &str, path: &str) -> Result<Url> {
+    let mut base = Url::parse(base_url).with_context(|| format!("bad base url {base_url}"))?;
+    // a mirror may live under a path, which join keeps only up to its last slash
+    if !base.path().ends_with('/') {
+        let dir = format!("{}/", base.path());
+        base.set_path(&dir);
+    }
+    Ok(base.join(path.trim_start_matches('/'))?)
+}
+
 pub fn setup_dir() -> Result<PathBuf> {
     (|| {
         let path = xdg::BaseDirectories::new()?.create_config_directory("xaoc")?;
@@ -76,3 +96,28 @@ impl Display for Part {
         write!(f, "{}", n)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url() -> Result<()> {
+        for (base, expected) in [
+            (
+                "https://adventofcode.com",
+                "https://adventofcode.com/2015/day/1",
+            ),
+            (
+                "https://adventofcode.com/",
+                "https://adventofcode.com/2015/day/1",
+            ),
+            ("http://host/mirror", "http://host/mirror/2015/day/1"),
+            ("http://host/mirror/", "http://host/mirror/2015/day/1"),
+        ] {
+            assert_eq!(super::url(base, "/2015/day/1")?.as_str(), expected);
+        }
+        assert!(super::url("not a url", "/2015").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+//! Recognition of the block letters some puzzles draw as their answer.
+
+struct Font {
+    height: usize,
+    /// Glyph width plus the gap before the next one.
+    pitch: usize,
+    glyphs: &'static [(char, &'static [&'static str])],
+}
+
+const SMALL: Font = Font {
+    height: 6,
+    pitch: 5,
+    glyphs: &[
+        ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+        ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+        ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+        ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+        ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+        ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+        ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+        ('I', &["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+        ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+        ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+        ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+        ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+        ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+        ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+        ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+        ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+        ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+    ],
+};
+
+#[rustfmt::skip]
+const LARGE: Font = Font {
+    height: 10,
+    pitch: 8,
+    glyphs: &[
+        ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#"]),
+        ('B', &["#####.", "#....#", "#....#", "#....#", "#####.", "#....#", "#....#", "#....#", "#....#", "#####."]),
+        ('C', &[".####.", "#....#", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#....#", ".####."]),
+        ('E', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('F', &["######", "#.....", "#.....", "#.....", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('G', &[".####.", "#....#", "#.....", "#.....", "#.....", "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+        ('H', &["#....#", "#....#", "#....#", "#....#", "######", "#....#", "#....#", "#....#", "#....#", "#....#"]),
+        ('J', &["...###", "....#.", "....#.", "....#.", "....#.", "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+        ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....", "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+        ('L', &["#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "#.....", "######"]),
+        ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#", "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+        ('P', &["#####.", "#....#", "#....#", "#....#", "#####.", "#.....", "#.....", "#.....", "#.....", "#....."]),
+        ('R', &["#####.", "#....#", "#....#", "#....#", "#####.", "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+        ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..", "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+        ('Z', &["######", ".....#", ".....#", "....#.", "...#..", "..#...", ".#....", "#.....", "#.....", "######"]),
+    ],
+};
+
+type Bitmap = Vec<Vec<bool>>;
+
+/// `.`, space and `0` are blank, anything else (`#`, `█`, `1`) is lit.
+fn pixels<'a>(lines: impl Iterator<Item = &'a str>) -> Bitmap {
+    let mut rows = lines
+        .map(|line| {
+            line.chars()
+                .map(|c| !matches!(c, '.' | ' ' | '0'))
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    for row in &mut rows {
+        row.resize(width, false);
+    }
+    rows
+}
+
+fn crop_rows(bitmap: &mut Bitmap) {
+    while bitmap.first().is_some_and(|row| !row.contains(&true)) {
+        bitmap.remove(0);
+    }
+    while bitmap.last().is_some_and(|row| !row.contains(&true)) {
+        bitmap.pop();
+    }
+}
+
+fn crop_cols(bitmap: &mut Bitmap) {
+    let lit = |col: usize| bitmap.iter().any(|row| row[col]);
+    let width = bitmap.first().map_or(0, |row| row.len());
+    let left = (0..width).find(|&col| lit(col)).unwrap_or(width);
+    let right = (0..width)
+        .rfind(|&col| lit(col))
+        .map_or(width, |col| col + 1);
+    for row in bitmap.iter_mut() {
+        row.truncate(right);
+        row.drain(..left.min(right));
+    }
+}
+
+impl Font {
+    fn glyph(&self, cell: &Bitmap) -> Option<char> {
+        self.glyphs
+            .iter()
+            .find(|(_, rows)| pixels(rows.iter().copied()) == *cell)
+            .map(|(c, _)| *c)
+    }
+
+    fn decode(&self, bitmap: &Bitmap) -> Option<String> {
+        if bitmap.len() != self.height {
+            return None;
+        }
+        let width = bitmap[0].len();
+        // the first letter may start with empty columns that got cropped
+        (0..self.pitch).find_map(|shift| {
+            let mut res = String::new();
+            let mut start = 0;
+            let mut end = self.pitch - shift;
+            while start < width {
+                let mut cell = bitmap
+                    .iter()
+                    .map(|row| row[start..end.min(width)].to_vec())
+                    .collect::<Vec<_>>();
+                crop_cols(&mut cell);
+                if cell[0].is_empty() {
+                    return None;
+                }
+                res.push(self.glyph(&cell)?);
+                start = end;
+                end += self.pitch;
+            }
+            Some(res)
+        })
+    }
+}
+
+/// Reads block letters drawn in either of the two fonts used by puzzles.
+pub fn recognize(s: &str) -> Option<String> {
+    let mut bitmap = pixels(s.lines());
+    crop_rows(&mut bitmap);
+    crop_cols(&mut bitmap);
+    SMALL.decode(&bitmap).or_else(|| LARGE.decode(&bitmap))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small() {
+        let s = "
+████  ██  ███  █  █ ████ █    ███  ████
+█    █  █ █  █ █ █     █ █    █  █    █
+███  █    █  █ ██     █  █    █  █   █
+█    █    ███  █ █   █   █    ███   █
+█    █  █ █ █  █ █  █    █    █ █  █
+████  ██  █  █ █  █ ████ ████ █  █ ████
+";
+        assert_eq!(recognize(s).as_deref(), Some("ECRKZLRZ"));
+    }
+
+    #[test]
+    fn small_leading_blank_columns() {
+        let s = "..##.#..#..##...###
+...#.#..#.#..#.#
+...#.#..#.#....#
+...#.#..#.#.##..##
+#..#.#..#.#..#....#
+.##...##...###.###";
+        assert_eq!(recognize(s).as_deref(), Some("JUGS"));
+    }
+
+    #[test]
+    fn large() {
+        let s = "#....#..#....#..#....#...####
+#...#...##...#..#....#..#....#
+#..#....##...#...#..#...#
+#.#.....#.#..#...#..#...#
+##......#.#..#....##....#
+##......#..#.#....##....#..###
+#.#.....#..#.#...#..#...#....#
+#..#....#...##...#..#...#....#
+#...#...#...##..#....#..#...##
+#....#..#....#..#....#...###.#";
+        assert_eq!(recognize(s).as_deref(), Some("KNXG"));
+    }
+
+    #[test]
+    fn garbage() {
+        assert_eq!(recognize("#..#\n.##.\n#..#"), None);
+        assert_eq!(recognize(""), None);
+    }
+}
@@ -1,19 +1,39 @@
 use crate::auth::{current_token, Token};
+use crate::calendar::Event;
+use crate::config::base_url;
 use crate::runner::year;
-use crate::{client, setup_dir, Day, Part, Year};
+use crate::{client, setup_dir, url, Day, Part, Year};
 use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use html2text::render::RichAnnotation;
+use itertools::Itertools;
 use regex::Regex;
 use reqwest::header::COOKIE;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::ffi::OsStr;
+use std::fmt::Display;
 use std::fs::{create_dir_all, File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const RENDER_WIDTH: usize = 80;
+const PREVIEW_LINES: usize = 5;
+const PREVIEW_WIDTH: usize = 60;
 
 lazy_static::lazy_static! {
     static ref TITLE_RE: Regex = Regex::new(r"--- (Day \d+?: .*?) ---").unwrap();
     static ref MAIN_RE: Regex = Regex::new(r"(?i)(?s)<main>(.*)</main>").unwrap();
+    static ref ARTICLE_RE: Regex =
+        Regex::new(r#"(?s)<article class="day-desc">(.*?)</article>"#).unwrap();
+    static ref GIVEN_ANSWER_RE: Regex =
+        Regex::new(r"(?s)<p>Your puzzle answer was .*?</p>").unwrap();
+    static ref PRE_RE: Regex = Regex::new(r"(?s)<pre><code>(.*?)</code></pre>").unwrap();
+    static ref EM_CODE_RE: Regex = Regex::new(r"(?s)<code><em>(.*?)</em></code>").unwrap();
+    static ref TOO_RECENT_RE: Regex =
+        Regex::new(r"(?i)you have (?:(\d+)m ?)?(?:(\d+)s )?left to wait").unwrap();
+    static ref WAIT_RE: Regex = Regex::new(r"(?i)wait (one|\d+) minutes? before trying again").unwrap();
 }
 
 #[derive(Serialize, Deserialize)]
@@ -23,7 +43,136 @@ pub struct Puzzle {
     pub text: String,
 }
 
+fn decode(s: &str) -> String {
+    html_escape::decode_html_entities(s)
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+impl Puzzle {
+    /// The description of each part unlocked when the page was fetched.
+    pub fn articles(&self) -> Vec<&str> {
+        ARTICLE_RE
+            .captures_iter(&self.text)
+            .map(|cap| cap.get(1).unwrap().as_str())
+            .collect()
+    }
+
+    /// Where the puzzle of `day` is cached in `root`. It's the same for all users.
+    fn path(root: &Path, year: Year, day: Day) -> PathBuf {
+        root.join(format!("puzzle/{year}/{day}/info.json"))
+    }
+
+    pub fn load(root: &Path, year: Year, day: Day) -> Result<Self> {
+        let path = Self::path(root, year, day);
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    /// Renders the description of part `part`, or of every part, followed by
+    /// the answer of `answers` given to it if any. Markdown keeps
+    /// `*emphasis*` and `` `code` `` marks, otherwise they're colored for the
+    /// terminal.
+    pub fn render(
+        &self,
+        part: Option<usize>,
+        markdown: bool,
+        answers: &[Option<String>],
+    ) -> Result<String> {
+        let articles = self.articles();
+        let picked = match part {
+            Some(part) => {
+                let i = part
+                    .checked_sub(1)
+                    .filter(|&i| i < articles.len())
+                    .ok_or_else(|| {
+                        anyhow!("part {part} isn't cached, solve part 1 or run prepare --refresh")
+                    })?;
+                vec![i]
+            }
+            None => (0..articles.len()).collect(),
+        };
+        let mut out = vec![];
+        for i in picked {
+            let mut html = articles[i].to_string();
+            if let Some(Some(answer)) = answers.get(i) {
+                html.push_str(&format!(
+                    "<p>Your puzzle answer was <code>{}</code>.</p>",
+                    html_escape::encode_text(answer)
+                ));
+            }
+            let text = if markdown {
+                html2text::from_read(html.as_bytes(), RENDER_WIDTH)?
+            } else {
+                html2text::config::rich().coloured(html.as_bytes(), RENDER_WIDTH, |tags, s| {
+                    let em = tags
+                        .iter()
+                        .any(|t| matches!(t, RichAnnotation::Emphasis | RichAnnotation::Strong));
+                    let code = tags
+                        .iter()
+                        .any(|t| matches!(t, RichAnnotation::Code | RichAnnotation::Preformat(_)));
+                    match (em, code) {
+                        (true, _) => s.yellow().bold().to_string(),
+                        (false, true) => s.cyan().to_string(),
+                        (false, false) => s.to_string(),
+                    }
+                })?
+            };
+            out.push(text);
+        }
+        Ok(out.join("\n"))
+    }
+
+    /// Number of parts whose description the page holds.
+    pub fn parts(&self) -> usize {
+        self.articles().len()
+    }
+
+    /// The `<pre><code>` blocks of the page, which hold the sample inputs.
+    pub fn samples(&self) -> Vec<String> {
+        PRE_RE
+            .captures_iter(&self.text)
+            .map(|cap| decode(&cap[1]))
+            .collect()
+    }
+
+    /// Guesses the sample answer of each part: the last emphasized `<code>`
+    /// of its article, paired with the closest `<pre><code>` block before it,
+    /// which may be in an earlier article.
+    pub fn sample_answers(&self) -> Vec<Option<(String, String)>> {
+        let mut block = None;
+        let mut guesses = vec![];
+        for article in self.articles() {
+            let blocks = PRE_RE
+                .captures_iter(article)
+                .map(|cap| (cap.get(0).unwrap().range(), cap.get(1).unwrap().as_str()))
+                .collect_vec();
+            let answer = EM_CODE_RE
+                .captures_iter(article)
+                .map(|cap| cap.get(0).unwrap().start())
+                .filter(|at| !blocks.iter().any(|(range, _)| range.contains(at)))
+                .last();
+            guesses.push(answer.and_then(|at| {
+                let sample = blocks
+                    .iter()
+                    .rev()
+                    .find(|(range, _)| range.end <= at)
+                    .map(|(_, sample)| *sample)
+                    .or(block)?;
+                let answer = EM_CODE_RE.captures(&article[at..]).unwrap();
+                Some((decode(sample), decode(&answer[1])))
+            }));
+            if let Some((_, sample)) = blocks.last() {
+                block = Some(sample);
+            }
+        }
+        guesses
+    }
+}
+
 pub struct Run {
+    event: Event,
+    root: PathBuf,
+    base_url: String,
     token: Token,
     year: Year,
     day: Day,
@@ -32,13 +181,25 @@ pub struct Run {
 
 impl Run {
     pub fn new(token: Token, year: Year, day: Day, part: Part) -> Result<Self> {
-        if !(2015..=2025).contains(&year.0) {
-            bail!("bad year {year}");
-        }
-        if !(1..=25).contains(&day.0) {
-            bail!("bad day {day}");
-        }
+        let root = setup_dir()?;
+        let base_url = base_url(&root)?;
+        Self::with_root(&root, &base_url, token, year, day, part)
+    }
+
+    pub fn with_root(
+        root: &Path,
+        base_url: &str,
+        token: Token,
+        year: Year,
+        day: Day,
+        part: Part,
+    ) -> Result<Self> {
+        let event = Event::load(root, year)?;
+        event.check_day(day)?;
         Ok(Run {
+            event,
+            root: root.to_path_buf(),
+            base_url: base_url.to_string(),
             token,
             year,
             day,
@@ -46,35 +207,53 @@ impl Run {
         })
     }
 
+    /// A run that only reads and writes the cache in `root`, with no server
+    /// to fetch from.
+    pub fn cached(root: &Path, token: Token, year: Year, day: Day, part: Part) -> Result<Self> {
+        Self::with_root(root, "", token, year, day, part)
+    }
+
+    pub fn unlock(&self) -> SystemTime {
+        self.event.unlock(self.day)
+    }
+
     fn puzzle_path(&self) -> Result<PathBuf> {
-        let mut base = setup_dir()?;
-        base.push(format!("puzzle/{}/{}/info.json", self.year, self.day));
-        Ok(base)
+        Ok(Puzzle::path(&self.root, self.year, self.day))
     }
 
     pub fn get_puzzle(&self) -> Result<Puzzle> {
-        let puzzle: Puzzle = serde_json::from_reader(File::open(self.puzzle_path()?)?)?;
-        Ok(puzzle)
+        Puzzle::load(&self.root, self.year, self.day)
     }
 
     pub fn get_or_fetch_puzzle(&self) -> Result<Puzzle> {
-        let path = self.puzzle_path()?;
-        if path.exists() {
+        if self.puzzle_path()?.exists() {
             let puzzle = self.get_puzzle()?;
-            if !puzzle.text.is_empty() {
+            // part 2 only shows up on the page once part 1 is solved
+            let stale = puzzle.parts() < 2 && self.base()?.join("1/answer").exists();
+            if !puzzle.text.is_empty() && !stale {
                 return Ok(puzzle);
             }
         }
-        create_dir_all(path.parent().unwrap())?;
+        self.fetch_puzzle()
+    }
+
+    /// Downloads the puzzle page as the user sees it, with every part they
+    /// unlocked, and caches it.
+    pub fn fetch_puzzle(&self) -> Result<Puzzle> {
         let client = client()?;
         let page = client
-            .get(format!(
-                "https://adventofcode.com/{}/day/{}",
-                self.year, self.day
-            ))
+            .get(self.url(&format!("/{}/day/{}", self.year, self.day))?)
+            .header(COOKIE, format!("session={}", self.token.token))
             .send()?
             .error_for_status()?
             .text()?;
+        self.set_puzzle(page)
+    }
+
+    /// Caches a puzzle page fetched with the user's session, without the
+    /// answers it shows, as the cache is shared by all users.
+    pub fn set_puzzle(&self, page: String) -> Result<Puzzle> {
+        let path = self.puzzle_path()?;
         let title = TITLE_RE
             .captures(&page)
             .ok_or_else(|| anyhow!("no title in puzzle page"))?
@@ -83,14 +262,19 @@ impl Run {
             .as_str();
         let puzzle = Puzzle {
             title: title.to_string(),
-            text: page,
+            text: GIVEN_ANSWER_RE.replace_all(&page, "").into_owned(),
         };
+        create_dir_all(path.parent().unwrap())?;
         serde_json::to_writer(&mut File::create(path)?, &puzzle)?;
         Ok(puzzle)
     }
 
+    fn url(&self, path: &str) -> Result<reqwest::Url> {
+        url(&self.base_url, path)
+    }
+
     fn base(&self) -> Result<PathBuf> {
-        let mut base = setup_dir()?;
+        let mut base = self.root.clone();
         base.push(format!(
             "user/{}/{}/{}/",
             self.token.id, self.year, self.day
@@ -110,6 +294,10 @@ impl Run {
         Ok(s.to_string())
     }
 
+    pub fn has_input(&self) -> Result<bool> {
+        Ok(self.input_path()?.exists())
+    }
+
     pub fn get_or_fetch_input(&self) -> Result<String> {
         let path = self.input_path()?;
         if path.exists() {
@@ -117,10 +305,7 @@ impl Run {
         }
         let client = client()?;
         let input = client
-            .get(format!(
-                "https://adventofcode.com/{}/day/{}/input",
-                self.year, self.day
-            ))
+            .get(self.url(&format!("/{}/day/{}/input", self.year, self.day))?)
             .header(COOKIE, format!("session={}", self.token.token))
             .send()?
             .error_for_status()?
@@ -171,25 +356,79 @@ impl Run {
         if self.is_bad_answer(res)? {
             return Ok(AnswerStatus::Bad);
         }
+        if let Ok(n) = res.parse::<i64>() {
+            let bounds = self.get_bounds()?;
+            if !bounds.contains(n) {
+                return Ok(AnswerStatus::OutOfBounds(bounds));
+            }
+        }
         Ok(AnswerStatus::Unknown)
     }
 
-    fn submit(&self, res: &str) -> Result<()> {
+    /// Submits `res` and returns the verdict along with the site's message
+    /// rendered as text.
+    pub fn submit(&self, res: &str, wait: bool) -> Result<(Verdict, String)> {
+        if !self.event.is_puzzle(self.day, self.part) {
+            bail!("day {} has no part {} to submit", self.day, self.part);
+        }
         match self.check_answer(res)? {
             AnswerStatus::Good => bail!("answer is good"),
             AnswerStatus::Bad => bail!("answer is bad"),
+            AnswerStatus::OutOfBounds(bounds) => bail!("answer is out of bounds {bounds}"),
             AnswerStatus::Unknown => (),
         }
+        loop {
+            if let Some(left) = self.cooldown_left()? {
+                if !wait {
+                    bail!("submitted too recently, {}s left to wait", left.as_secs());
+                }
+                eprintln!("waiting {}s for cooldown", left.as_secs());
+                std::thread::sleep(left);
+            }
+            let main = self.post_answer(res)?;
+            let verdict = Verdict::parse(&main);
+            match verdict {
+                Verdict::Right => {
+                    self.set_answer(res)?;
+                    if let Part::One = self.part {
+                        if let Err(e) = self.fetch_puzzle() {
+                            eprintln!("could not fetch part 2: {e}");
+                        }
+                    }
+                }
+                Verdict::Wrong {
+                    hint,
+                    wait: cooldown,
+                } => {
+                    self.add_bad_answer(res)?;
+                    if let Some(hint) = hint {
+                        self.add_bound(res, hint)?;
+                    }
+                    if let Some(cooldown) = cooldown {
+                        self.set_cooldown(cooldown)?;
+                    }
+                }
+                Verdict::TooRecent(left) => {
+                    self.set_cooldown(left)?;
+                    if wait {
+                        continue;
+                    }
+                }
+                Verdict::Other => (),
+            }
+            let main = html2text::from_read(main.as_bytes(), 80)?;
+            return Ok((verdict, main));
+        }
+    }
+
+    fn post_answer(&self, res: &str) -> Result<String> {
         let client = client()?;
         let params = HashMap::<&str, String>::from_iter([
             ("level", self.part.to_string()),
             ("answer", res.to_string()),
         ]);
         let resp = client
-            .post(format!(
-                "https://adventofcode.com/{}/day/{}/answer",
-                self.year, self.day
-            ))
+            .post(self.url(&format!("/{}/day/{}/answer", self.year, self.day))?)
             .header(COOKIE, format!("session={}", self.token.token))
             .form(&params)
             .send()?
@@ -201,14 +440,83 @@ impl Run {
             .get(1)
             .unwrap()
             .as_str();
-        if main.contains("not the right answer") {
-            self.add_bad_answer(res)?;
-            println!("added bad answer {res}");
-        } else if main.contains("That's the right answer") {
-            self.set_answer(res)?;
+        Ok(main.to_string())
+    }
+
+    fn multiline_path(&self) -> Result<PathBuf> {
+        let mut path = self.base()?;
+        path.push(format!("{}/multiline", self.part));
+        Ok(path)
+    }
+
+    /// Saves an unrecognized multiline answer for `xaoc map`.
+    pub fn set_multiline(&self, res: &str) -> Result<PathBuf> {
+        let path = self.multiline_path()?;
+        create_dir_all(path.parent().unwrap())?;
+        std::fs::write(&path, res)?;
+        Ok(path)
+    }
+
+    fn get_multiline(&self) -> Result<String> {
+        let path = self.multiline_path()?;
+        std::fs::read_to_string(&path).with_context(|| format!("read {path:?}"))
+    }
+
+    fn cooldown_path(&self) -> Result<PathBuf> {
+        let mut path = self.base()?;
+        path.push(format!("{}/cooldown", self.part));
+        Ok(path)
+    }
+
+    /// Time left before the site accepts another answer, if any.
+    pub fn cooldown_left(&self) -> Result<Option<Duration>> {
+        let path = self.cooldown_path()?;
+        if !path.exists() {
+            return Ok(None);
         }
-        let main = html2text::from_read(main.as_bytes(), 80)?;
-        println!("\n{main}");
+        let deadline: u64 = std::fs::read_to_string(path)?.trim().parse()?;
+        let deadline = UNIX_EPOCH + Duration::from_secs(deadline);
+        Ok(deadline.duration_since(SystemTime::now()).ok())
+    }
+
+    fn set_cooldown(&self, left: Duration) -> Result<()> {
+        let path = self.cooldown_path()?;
+        create_dir_all(path.parent().unwrap())?;
+        // round up so we never retry a moment too early
+        let deadline = (SystemTime::now() + left)
+            .duration_since(UNIX_EPOCH)?
+            .as_secs()
+            + 1;
+        std::fs::write(path, deadline.to_string())?;
+        Ok(())
+    }
+
+    fn bounds_path(&self) -> Result<PathBuf> {
+        let mut path = self.base()?;
+        path.push(format!("{}/bounds.json", self.part));
+        Ok(path)
+    }
+
+    pub fn get_bounds(&self) -> Result<Bounds> {
+        let path = self.bounds_path()?;
+        if !path.exists() {
+            return Ok(Bounds::default());
+        }
+        Ok(serde_json::from_reader(File::open(path)?)?)
+    }
+
+    fn add_bound(&self, res: &str, hint: Hint) -> Result<()> {
+        let Ok(n) = res.parse::<i64>() else {
+            return Ok(());
+        };
+        let mut bounds = self.get_bounds()?;
+        match hint {
+            Hint::TooLow => bounds.low = Some(bounds.low.map_or(n, |low| low.max(n))),
+            Hint::TooHigh => bounds.high = Some(bounds.high.map_or(n, |high| high.min(n))),
+        }
+        let path = self.bounds_path()?;
+        create_dir_all(path.parent().unwrap())?;
+        serde_json::to_writer(&mut File::create(path)?, &bounds)?;
         Ok(())
     }
 
@@ -231,14 +539,167 @@ impl Run {
 pub enum AnswerStatus {
     Good,
     Bad,
+    /// Not submitted, but ruled out by earlier too high/too low answers.
+    OutOfBounds(Bounds),
     Unknown,
 }
 
-pub fn submit(day: u16, part: u16, res: &str) -> Result<()> {
+/// Known limits on a numeric answer, both exclusive.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bounds {
+    /// Largest answer the site called too low.
+    pub low: Option<i64>,
+    /// Smallest answer the site called too high.
+    pub high: Option<i64>,
+}
+
+impl Bounds {
+    pub fn contains(&self, n: i64) -> bool {
+        self.low.is_none_or(|low| n > low) && self.high.is_none_or(|high| n < high)
+    }
+}
+
+impl Display for Bounds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let low = self.low.map(|low| format!("> {low}"));
+        let high = self.high.map(|high| format!("< {high}"));
+        write!(f, "{}", low.into_iter().chain(high).join(", "))
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Hint {
+    TooHigh,
+    TooLow,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Right,
+    Wrong {
+        hint: Option<Hint>,
+        wait: Option<Duration>,
+    },
+    TooRecent(Duration),
+    Other,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Verdict::Right => write!(f, "✅ right"),
+            Verdict::Wrong {
+                hint: Some(Hint::TooHigh),
+                ..
+            } => write!(f, "❌ too high"),
+            Verdict::Wrong {
+                hint: Some(Hint::TooLow),
+                ..
+            } => write!(f, "❌ too low"),
+            Verdict::Wrong { hint: None, .. } => write!(f, "❌ wrong"),
+            Verdict::TooRecent(left) => write!(f, "⏳ too recent, {}s left", left.as_secs()),
+            Verdict::Other => write!(f, "❔ unexpected response"),
+        }
+    }
+}
+
+impl Verdict {
+    fn parse(main: &str) -> Self {
+        if main.contains("That's the right answer") {
+            return Verdict::Right;
+        }
+        if let Some(cap) = TOO_RECENT_RE.captures(main) {
+            let num = |i| {
+                cap.get(i)
+                    .map_or(0, |m: regex::Match| m.as_str().parse().unwrap())
+            };
+            return Verdict::TooRecent(Duration::from_secs(num(1) * 60 + num(2)));
+        }
+        if main.contains("not the right answer") {
+            let hint = if main.contains("your answer is too high") {
+                Some(Hint::TooHigh)
+            } else if main.contains("your answer is too low") {
+                Some(Hint::TooLow)
+            } else {
+                None
+            };
+            let wait = WAIT_RE.captures(main).map(|cap| {
+                let mins = match &cap[1] {
+                    "one" => 1,
+                    n => n.parse().unwrap(),
+                };
+                Duration::from_secs(mins * 60)
+            });
+            return Verdict::Wrong { hint, wait };
+        }
+        Verdict::Other
+    }
+}
+
+/// Prints the cached description of a day, or of one of its parts.
+pub fn show(day: u16, part: Option<u16>, markdown: bool) -> Result<()> {
+    let year = year()?;
+    let root = setup_dir()?;
+    let puzzle =
+        Puzzle::load(&root, year, Day(day)).context("no cached puzzle, run prepare first")?;
+    // the text reads fine without answers, so a token is optional
+    let mut answers = vec![];
+    if let Ok(token) = current_token() {
+        for n in 1..=puzzle.parts() as u16 {
+            let run = Run::cached(&root, token.clone(), year, Day(day), Part::new(n)?)?;
+            answers.push(run.get_answer().ok());
+        }
+    }
+    print!(
+        "{}",
+        puzzle.render(part.map(|p| p as usize), markdown, &answers)?
+    );
+    Ok(())
+}
+
+/// Lists the sample blocks of a day with a preview and the answers guessed
+/// for them, numbered for `--sample <n>`.
+pub fn samples(day: u16) -> Result<()> {
+    let year = year()?;
+    let token = current_token()?;
+    let run = Run::new(token, year, Day(day), Part::One)?;
+    let puzzle = run.get_or_fetch_puzzle()?;
+    let guesses = puzzle.sample_answers();
+    let samples = puzzle.samples();
+    if samples.is_empty() {
+        println!("no sample blocks");
+    }
+    for (idx, sample) in samples.iter().enumerate() {
+        let lines = sample.lines().collect_vec();
+        let mut header = format!("{idx}: {} lines", lines.len());
+        for (part, guess) in guesses.iter().enumerate() {
+            if let Some((_, answer)) = guess.as_ref().filter(|(input, _)| input == sample) {
+                header.push_str(&format!(", part {} answer {answer}?", part + 1));
+            }
+        }
+        println!("{header}");
+        for line in lines.iter().take(PREVIEW_LINES) {
+            println!(
+                "    {}",
+                line.chars().take(PREVIEW_WIDTH).collect::<String>()
+            );
+        }
+        if lines.len() > PREVIEW_LINES {
+            println!("    ...");
+        }
+    }
+    Ok(())
+}
+
+pub fn submit(day: u16, part: u16, res: &str, wait: bool) -> Result<()> {
     let year = year()?;
     let token = current_token()?;
     let run = Run::new(token, year, Day(day), Part::new(part)?)?;
-    run.submit(res)?;
+    let (verdict, main) = run.submit(res, wait)?;
+    if let Verdict::Wrong { .. } = verdict {
+        println!("added bad answer {res}");
+    }
+    println!("\n{main}");
     Ok(())
 }
 
@@ -276,9 +737,12 @@ impl Map {
     }
 }
 
-pub fn map(res: &str) -> Result<()> {
+pub fn map(day: u16, part: u16, res: &str) -> Result<()> {
+    let year = year()?;
+    let token = current_token()?;
+    let run = Run::new(token, year, Day(day), Part::new(part)?)?;
     let mut map = Map::new()?;
-    let ml = std::fs::read_to_string("/tmp/xaoc_multiline").context("read /tmp/xaoc_multiline")?;
+    let ml = run.get_multiline()?;
     println!("mapping\n{ml}\nto");
     println!("{res}");
     map.map.insert(ml, res.to_string());
@@ -286,9 +750,12 @@ pub fn map(res: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn unmap() -> Result<()> {
+pub fn unmap(day: u16, part: u16) -> Result<()> {
+    let year = year()?;
+    let token = current_token()?;
+    let run = Run::new(token, year, Day(day), Part::new(part)?)?;
     let mut map = Map::new()?;
-    let ml = std::fs::read_to_string("/tmp/xaoc_multiline").context("read /tmp/xaoc_multiline")?;
+    let ml = run.get_multiline()?;
     println!("unmapping\n{ml}");
     map.map.remove(&ml);
     map.write()?;
@@ -299,3 +766,221 @@ pub fn map_get(res: &str) -> Result<Option<String>> {
     let map = Map::new()?;
     Ok(map.map.get(res).map(|s| s.to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::Site;
+    use mockito::Matcher;
+
+    #[test]
+    fn fetch_puzzle_and_input() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let puzzle_mock = server
+            .mock("GET", "/2015/day/1")
+            .match_header("cookie", "session=F000")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1.html"))
+            .create();
+        let input_mock = server
+            .mock("GET", "/2015/day/1/input")
+            .match_header("cookie", "session=F000")
+            .with_status(200)
+            .with_body("(()(()(\n")
+            .create();
+        let run = site.run(1, Part::One)?;
+        assert_eq!(run.get_or_fetch_puzzle()?.title, "Day 1: Not Quite Lisp");
+        assert_eq!(run.get_or_fetch_input()?.trim_end(), "(()(()(");
+        // second round is served from the cache
+        assert_eq!(run.get_or_fetch_puzzle()?.title, "Day 1: Not Quite Lisp");
+        assert_eq!(run.get_or_fetch_input()?, "(()(()(");
+        puzzle_mock.assert();
+        input_mock.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn sample_answers() {
+        let puzzle = Puzzle {
+            title: "Day 1: Calorie Counting".into(),
+            text: include_str!("../fixtures/puzzle_2022_1_solved.html").into(),
+        };
+        let sample = "1000\n2000\n3000\n\n4000\n\n5000\n6000\n\n7000\n8000\n9000\n\n10000";
+        assert_eq!(puzzle.samples(), [sample]);
+        assert_eq!(
+            puzzle.sample_answers(),
+            [
+                Some((sample.into(), "24000".into())),
+                Some((sample.into(), "45000".into()))
+            ]
+        );
+        let puzzle = Puzzle {
+            title: "Day 1: Not Quite Lisp".into(),
+            text: include_str!("../fixtures/puzzle_2015_1_solved.html").into(),
+        };
+        assert_eq!(puzzle.sample_answers(), [None, None]);
+        assert!(puzzle.samples().is_empty());
+    }
+
+    #[test]
+    fn render() -> Result<()> {
+        let puzzle = Puzzle {
+            title: "Day 1: Calorie Counting".into(),
+            text: include_str!("../fixtures/puzzle_2022_1_solved.html").into(),
+        };
+        let answers = [Some("71780".to_string()), Some("212489".to_string())];
+        let all = puzzle.render(None, true, &answers)?;
+        assert!(all.starts_with("## --- Day 1: Calorie Counting ---"));
+        assert!(all.contains("a total\nof `*6000*` Calories"));
+        assert!(all.contains("Your puzzle answer was `71780`."));
+        assert!(all.ends_with("Your puzzle answer was `212489`.\n"));
+        let part2 = puzzle.render(Some(2), true, &answers)?;
+        assert!(part2.starts_with("## --- Part Two ---"));
+        assert!(!part2.contains("71780"));
+        // the answers on the page are some user's, not necessarily the reader's
+        let other = puzzle.render(None, true, &[None, Some("1".to_string())])?;
+        assert!(!other.contains("71780"));
+        assert!(other.ends_with("Your puzzle answer was `1`.\n"));
+        assert!(puzzle.render(Some(3), true, &answers).is_err());
+        assert!(puzzle.render(Some(0), true, &answers).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn refetch_puzzle_after_part_one() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let run = site.run(1, Part::One)?;
+        let unsolved_mock = server
+            .mock("GET", "/2015/day/1")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1.html"))
+            .create();
+        assert_eq!(run.get_or_fetch_puzzle()?.parts(), 1);
+        unsolved_mock.assert();
+        unsolved_mock.remove();
+        let submit_mock = server
+            .mock("POST", "/2015/day/1/answer")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/submit_good.html"))
+            .create();
+        let solved_mock = server
+            .mock("GET", "/2015/day/1")
+            .match_header("cookie", "session=F000")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1_solved.html"))
+            .create();
+        assert_eq!(run.submit("280", false)?.0, Verdict::Right);
+        // served from the cache the submit refreshed
+        let puzzle = run.get_or_fetch_puzzle()?;
+        assert_eq!(puzzle.parts(), 2);
+        assert!(!puzzle.text.contains("Your puzzle answer was"));
+        submit_mock.assert();
+        solved_mock.assert();
+        Ok(())
+    }
+
+    #[test]
+    fn submit_bad_then_good() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let run = site.run(1, Part::One)?;
+        let bad_mock = server
+            .mock("POST", "/2015/day/1/answer")
+            .match_header("cookie", "session=F000")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("level".into(), "1".into()),
+                Matcher::UrlEncoded("answer".into(), "17".into()),
+            ]))
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/submit_bad.html"))
+            .create();
+        assert_eq!(
+            run.submit("17", false)?.0,
+            Verdict::Wrong {
+                hint: None,
+                wait: Some(Duration::from_secs(60))
+            }
+        );
+        bad_mock.assert();
+        assert!(matches!(run.check_answer("17")?, AnswerStatus::Bad));
+        assert!(run.submit("17", false).is_err());
+        assert!(run.cooldown_left()?.is_some());
+        std::fs::remove_file(run.cooldown_path()?)?;
+
+        let good_mock = server
+            .mock("POST", "/2015/day/1/answer")
+            .match_body(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("level".into(), "1".into()),
+                Matcher::UrlEncoded("answer".into(), "280".into()),
+            ]))
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/submit_good.html"))
+            .create();
+        assert_eq!(run.submit("280", false)?.0, Verdict::Right);
+        good_mock.assert();
+        assert!(matches!(run.check_answer("280")?, AnswerStatus::Good));
+        Ok(())
+    }
+
+    #[test]
+    fn submit_too_high_and_too_recent() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let run = site.run(1, Part::One)?;
+        let _m = server
+            .mock("POST", "/2015/day/1/answer")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/submit_too_recent.html"))
+            .create();
+        assert_eq!(
+            run.submit("300", false)?.0,
+            Verdict::TooRecent(Duration::from_secs(34))
+        );
+        assert!(matches!(run.check_answer("300")?, AnswerStatus::Unknown));
+        assert!(run.submit("300", false).is_err());
+        std::fs::remove_file(run.cooldown_path()?)?;
+
+        let _m = server
+            .mock("POST", "/2015/day/1/answer")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/submit_too_high.html"))
+            .create();
+        run.submit("300", false)?;
+        std::fs::remove_file(run.cooldown_path()?)?;
+        run.submit("290", false)?;
+        assert_eq!(
+            run.get_bounds()?,
+            Bounds {
+                low: None,
+                high: Some(290)
+            }
+        );
+        assert!(matches!(
+            run.check_answer("295")?,
+            AnswerStatus::OutOfBounds(_)
+        ));
+        assert!(matches!(run.check_answer("289")?, AnswerStatus::Unknown));
+        assert!(run.submit("295", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_verdict() {
+        assert_eq!(
+            Verdict::parse("You have 1m 23s left to wait."),
+            Verdict::TooRecent(Duration::from_secs(83))
+        );
+        assert_eq!(
+            Verdict::parse(
+                "That's not the right answer; your answer is too low. \
+                 Please wait 5 minutes before trying again."
+            ),
+            Verdict::Wrong {
+                hint: Some(Hint::TooLow),
+                wait: Some(Duration::from_secs(300))
+            }
+        );
+        assert_eq!(
+            Verdict::parse("You don't seem to be solving the right level."),
+            Verdict::Other
+        );
+    }
+}
@@ -1,17 +1,30 @@
-use crate::auth::current_token;
-use crate::puzzle::{AnswerStatus, Run};
-use crate::{client, Day, Part, Year};
+use crate::auth::{current_token, find_token, tokens, Token};
+use crate::bench::bench;
+use crate::calendar::{started_years, wait_until, Clock, Event, SystemClock};
+use crate::config::{base_url, Config};
+use crate::history::{self, Record, Status};
+use crate::jobs::{self, Limits, Outcome};
+use crate::puzzle::{AnswerStatus, Puzzle, Run, Verdict};
+use crate::{client, setup_dir, url, Day, Part, Year};
 use anyhow::{anyhow, bail, Context, Result};
 use colored::Colorize;
 use itertools::Itertools;
 use regex::Regex;
 use reqwest::header::COOKIE;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::Display;
+use std::fs::File;
 use std::io::Write;
+use std::ops::RangeInclusive;
 use std::os::unix::prelude::PermissionsExt;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
-use std::process::Command;
-use std::time::Instant;
+use std::process::{Command, Stdio};
+use std::sync::Once;
+use std::time::{Duration, Instant, SystemTime};
 
 lazy_static::lazy_static! {
     static ref EXE_RE: Regex = Regex::new(r"^(\d{4})_(\d{1,2})$").unwrap();
@@ -47,21 +60,86 @@ pub fn parse_day(file: &str) -> Day {
     Day(day)
 }
 
+/// Generates `main` for a day, plus `cargo test` checks of both parts
+/// against their sample answers and the cached answers.
 #[macro_export]
 macro_rules! xaoc {
-    ( $($i:ident = $e:expr),* ) => {
+    ( parse = $parse:expr $(, $i:ident = $e:expr)* ) => {
         use anyhow::Result;
 
+        fn xaoc_opts() -> xaoc::runner::RunOptions {
+            #[allow(unused_mut)]
+            let mut opts = xaoc::runner::RunOptions::default();
+            $(
+                opts.$i = $e;
+            )*
+            opts
+        }
+
         fn main() -> Result<()> {
             let year = xaoc::runner::year()?;
             let day = xaoc::runner::parse_day(std::file!());
+            // closures so parts can take e.g. `&[T]` for a `Vec<T>`
+            xaoc::runner::run_parsed(year, day, $parse, |t| part1(t), |t| part2(t), xaoc_opts())?;
+            Ok(())
+        }
+
+        #[cfg(test)]
+        mod xaoc_tests {
+            use super::*;
+
+            #[test]
+            fn part1() -> Result<()> {
+                let year = xaoc::runner::year()?;
+                let day = xaoc::runner::parse_day(std::file!());
+                xaoc::runner::check_parsed(year, day, 1, $parse, |t| super::part1(t), &xaoc_opts())
+            }
+
+            #[test]
+            fn part2() -> Result<()> {
+                let year = xaoc::runner::year()?;
+                let day = xaoc::runner::parse_day(std::file!());
+                xaoc::runner::check_parsed(year, day, 2, $parse, |t| super::part2(t), &xaoc_opts())
+            }
+        }
+    };
+    ( $($i:ident = $e:expr),* ) => {
+        use anyhow::Result;
+
+        fn xaoc_opts() -> xaoc::runner::RunOptions {
+            #[allow(unused_mut)]
             let mut opts = xaoc::runner::RunOptions::default();
             $(
                 opts.$i = $e;
             )*
-            xaoc::runner::run(year, day, part1, part2, opts)?;
+            opts
+        }
+
+        fn main() -> Result<()> {
+            let year = xaoc::runner::year()?;
+            let day = xaoc::runner::parse_day(std::file!());
+            xaoc::runner::run(year, day, part1, part2, xaoc_opts())?;
             Ok(())
         }
+
+        #[cfg(test)]
+        mod xaoc_tests {
+            use super::*;
+
+            #[test]
+            fn part1() -> Result<()> {
+                let year = xaoc::runner::year()?;
+                let day = xaoc::runner::parse_day(std::file!());
+                xaoc::runner::check(year, day, 1, super::part1, &xaoc_opts())
+            }
+
+            #[test]
+            fn part2() -> Result<()> {
+                let year = xaoc::runner::year()?;
+                let day = xaoc::runner::parse_day(std::file!());
+                xaoc::runner::check(year, day, 2, super::part2, &xaoc_opts())
+            }
+        }
     };
 }
 
@@ -78,8 +156,123 @@ struct Cli {
     #[arg(long)]
     dev: bool,
 
+    /// Like --dev, but only on sample block <n> as listed by `xaoc samples`
+    #[arg(long, value_name = "n")]
+    sample: Option<usize>,
+
+    /// Run on this file instead of the cached input, `-` for stdin
     #[arg(long)]
     input_file: Option<PathBuf>,
+
+    /// Don't touch the config dir: run on --input-file or stdin without answer checks
+    #[arg(long)]
+    offline: bool,
+
+    /// Use this token instead of the current one, by index in `xaoc auth list` or user id
+    #[arg(long, value_name = "idx|id", conflicts_with = "offline")]
+    user: Option<String>,
+
+    /// Submit the answer for the part picked with --p1 or --p2 if it's not known yet
+    #[arg(long)]
+    submit: bool,
+
+    /// Time each part over many runs instead of once
+    #[arg(long)]
+    bench: bool,
+
+    /// Number of timed runs for --bench, instead of filling --bench-budget
+    #[arg(long)]
+    bench_runs: Option<usize>,
+
+    /// Seconds to spend on each part with --bench
+    #[arg(long, default_value_t = 3.0)]
+    bench_budget: f64,
+
+    #[arg(long, value_enum, default_value_t)]
+    format: Format,
+}
+
+#[derive(clap::ValueEnum, Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Text,
+    /// One JSON object per part
+    Json,
+    /// One tab-separated line per part: year, day, part, status, micros, answer, error, parse micros
+    Tsv,
+}
+
+impl Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Tsv => "tsv",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// Outcome of one part for --format json/tsv.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PartResult {
+    pub year: u16,
+    pub day: u16,
+    pub part: u16,
+    pub answer: String,
+    pub status: Status,
+    pub micros: u64,
+    /// Shared parse time of days using `parse`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parse_micros: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl PartResult {
+    fn print(&self, format: Format) -> Result<()> {
+        match format {
+            Format::Text => {
+                let sep = if self.answer.contains('\n') {
+                    "\n"
+                } else {
+                    " "
+                };
+                println!(
+                    "{} day {:>2} part {} {} {:.3}ms{sep}[ {} ]{}",
+                    self.year,
+                    self.day,
+                    self.part,
+                    self.status.symbol(),
+                    self.micros as f64 / 1000.0,
+                    self.answer,
+                    self.error
+                        .as_ref()
+                        .map(|e| format!(" {e}"))
+                        .unwrap_or_default(),
+                );
+            }
+            Format::Json => println!("{}", serde_json::to_string(self)?),
+            Format::Tsv => {
+                let escape = |s: &str| {
+                    s.replace('\\', "\\\\")
+                        .replace('\n', "\\n")
+                        .replace('\t', "\\t")
+                };
+                println!(
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    self.year,
+                    self.day,
+                    self.part,
+                    self.status,
+                    self.micros,
+                    escape(&self.answer),
+                    escape(self.error.as_deref().unwrap_or("")),
+                    self.parse_micros.map(|p| p.to_string()).unwrap_or_default(),
+                );
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Default)]
@@ -89,6 +282,14 @@ pub struct RunOptions {
     pub sample: &'static str,
     pub sample2: &'static str,
     pub no_sample: bool,
+    /// Answer part 1 should give on its sample in --dev.
+    pub expected: &'static str,
+    /// Answer part 2 should give on its sample in --dev.
+    pub expected2: &'static str,
+    /// More `(input, answer)` pairs to check part 1 against in --dev.
+    pub samples: &'static [(&'static str, &'static str)],
+    /// More `(input, answer)` pairs to check part 2 against in --dev.
+    pub samples2: &'static [(&'static str, &'static str)],
 }
 
 impl RunOptions {
@@ -111,6 +312,98 @@ impl RunOptions {
     pub fn no_sample(&mut self, no_sample: bool) {
         self.no_sample = no_sample;
     }
+
+    pub fn expected(&mut self, expected: &'static str) {
+        self.expected = expected;
+    }
+
+    pub fn expected2(&mut self, expected2: &'static str) {
+        self.expected2 = expected2;
+    }
+
+    pub fn samples(&mut self, samples: &'static [(&'static str, &'static str)]) {
+        self.samples = samples;
+    }
+
+    pub fn samples2(&mut self, samples2: &'static [(&'static str, &'static str)]) {
+        self.samples2 = samples2;
+    }
+}
+
+/// The sample input picked for part `n`, if any.
+fn pick_sample(opts: &RunOptions, text: &str, n: u16) -> Option<String> {
+    if opts.no_sample {
+        return None;
+    }
+    if n == 2 {
+        if !opts.sample2.is_empty() {
+            return Some(opts.sample2.to_string());
+        }
+        if let Some(idx) = opts.sample_idx2 {
+            return get_sample(text, idx);
+        }
+    }
+    if !opts.sample.is_empty() {
+        Some(opts.sample.to_string())
+    } else {
+        get_sample(text, opts.sample_idx)
+    }
+}
+
+/// An input to try a part on in --dev, with the answer it should give if known.
+#[derive(Debug, PartialEq)]
+struct Sample {
+    input: String,
+    expected: Option<String>,
+    /// `expected` was read off the puzzle text rather than declared.
+    guessed: bool,
+}
+
+impl Sample {
+    fn new(input: &str, expected: Option<&str>) -> Self {
+        Sample {
+            input: input.to_string(),
+            expected: expected.map(|s| s.to_string()),
+            guessed: false,
+        }
+    }
+}
+
+/// Inputs to try part `n` on in --dev: the picked sample first, then the
+/// extra `samples`.
+fn dev_samples(opts: &RunOptions, text: &str, n: u16) -> Vec<Sample> {
+    let (expected, extra) = match n {
+        1 => (opts.expected, opts.samples),
+        _ => (opts.expected2, opts.samples2),
+    };
+    let expected = (!expected.is_empty()).then_some(expected);
+    pick_sample(opts, text, n)
+        .map(|sample| Sample::new(&sample, expected))
+        .into_iter()
+        .chain(extra.iter().map(|(i, a)| Sample::new(i, Some(a))))
+        .collect()
+}
+
+/// Fills in the answer guessed from the puzzle text for the sample it belongs
+/// to, adding that sample if it isn't tried yet.
+fn add_guess(samples: &mut Vec<Sample>, guess: Option<(String, String)>) {
+    let Some((input, answer)) = guess else {
+        return;
+    };
+    match samples.iter_mut().find(|s| s.input == input) {
+        Some(Sample {
+            expected: Some(_), ..
+        }) => {}
+        Some(sample) => {
+            sample.expected = Some(answer);
+            sample.guessed = true;
+        }
+        None => samples.push(Sample {
+            input,
+            expected: Some(answer),
+            guessed: true,
+        }),
+    }
 }
 
 fn get_sample(text: &str, idx: usize) -> Option<String> {
@@ -123,6 +416,122 @@ fn get_sample(text: &str, idx: usize) -> Option<String> {
     Some(s)
 }
 
+/// Reads an input given on the command line, `-` or none meaning stdin.
+fn read_input(path: Option<&Path>) -> Result<String> {
+    let s = match path {
+        Some(path) if path != Path::new("-") => String::from_utf8(std::fs::read(path)?)?,
+        _ => std::io::read_to_string(std::io::stdin())?,
+    };
+    Ok(s.trim_end_matches('\n').to_string())
+}
+
+/// What a part function can return: a printable answer, or a `Result` of one
+/// so solutions can use `?`.
+pub trait Answer {
+    fn into_answer(self) -> Result<String, String>;
+}
+
+macro_rules! display_answer {
+    ( $($t:ty),* ) => {
+        $(
+            impl Answer for $t {
+                fn into_answer(self) -> Result<String, String> {
+                    Ok(self.to_string())
+                }
+            }
+        )*
+    };
+}
+
+display_answer!(
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    f32,
+    f64,
+    bool,
+    char,
+    String,
+    &String,
+    &str,
+    Box<dyn Display>
+);
+
+impl<T: Answer, E: Display> Answer for Result<T, E> {
+    fn into_answer(self) -> Result<String, String> {
+        match self {
+            Ok(res) => res.into_answer(),
+            // alternate format shows the whole anyhow context chain
+            Err(e) => Err(format!("{e:#}")),
+        }
+    }
+}
+
+thread_local! {
+    /// Set while the thread is in [`run_part`], which reports its panics instead.
+    static CAPTURE: Cell<bool> = const { Cell::new(false) };
+    static PANIC: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
+/// Runs one part, turning a panic or a returned error into a failure status
+/// with a message.
+fn run_part<T>(f: &dyn Fn() -> Result<T, String>) -> Result<T, (Status, String)> {
+    // the hook is process-wide, so set it once and leave other threads to the default one
+    PANIC_HOOK.call_once(|| {
+        let prev = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            if !CAPTURE.get() {
+                return prev(info);
+            }
+            let payload = info.payload();
+            let msg = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(|s| s.as_str()))
+                .unwrap_or("Box<dyn Any>");
+            let msg = match info.location() {
+                Some(loc) => format!("panicked at {loc}: {msg}"),
+                None => format!("panicked: {msg}"),
+            };
+            PANIC.set(Some(msg));
+        }));
+    });
+    let capturing = CAPTURE.replace(true);
+    let res = std::panic::catch_unwind(AssertUnwindSafe(f));
+    CAPTURE.set(capturing);
+    match res {
+        Ok(Ok(res)) => Ok(res),
+        Ok(Err(e)) => Err((Status::Error, e)),
+        Err(_) => Err((Status::Panic, PANIC.take().unwrap_or_default())),
+    }
+}
+
+/// The string a multiline drawing stands for, read by OCR or from the map.
+fn read_letters(res: &str) -> Result<Option<String>> {
+    match crate::ocr::recognize(res) {
+        Some(s) => Ok(Some(s)),
+        None => crate::puzzle::map_get(res),
+    }
+}
+
+fn describe(res: Result<String, (Status, String)>) -> String {
+    match res {
+        Ok(res) => res,
+        Err((status, msg)) => format!("{} {msg}", status.symbol()),
+    }
+}
+
 pub fn run<F1, D1, F2, D2>(
     year: Year,
     day: Day,
@@ -132,163 +541,778 @@ pub fn run<F1, D1, F2, D2>(
 ) -> Result<()>
 where
     F1: Fn(&str) -> D1,
-    D1: Display,
+    D1: Answer,
     F2: Fn(&str) -> D2,
-    D2: Display,
+    D2: Answer,
 {
-    let cli = Cli::parse();
-    let mut p1 = cli.p1;
-    let mut p2 = cli.p2;
-    if !p1 && !p2 {
-        p1 = true;
-        p2 = true;
-    }
-
-    let token = crate::auth::current_token().context("get token")?;
-    let run = Run::new(token.clone(), year, day, Part::One)?;
-    let puzzle = run.get_puzzle().context("get puzzle")?;
-    let input = if let Some(path) = cli.input_file {
-        let s = String::from_utf8(std::fs::read(path)?)?;
-        let s = s.trim_end_matches('\n');
-        s.to_string()
-    } else {
-        run.get_input().context("get input")?
-    };
-    println!("{}", puzzle.title.green().bold());
-    if cli.dev {
-        (|| {
-            if puzzle.text.is_empty() {
-                println!("no puzzle text, run prepare first");
-                return;
-            }
-            if opts.no_sample {
-                println!("skipping sample");
-                return;
-            }
-            let input_p1 = if !opts.sample.is_empty() {
-                opts.sample.to_string()
-            } else if let Some(input_p1) = get_sample(&puzzle.text, opts.sample_idx) {
-                input_p1
-            } else {
-                println!("no p1 input");
-                return;
-            };
-            let input_p2 = if !opts.sample2.is_empty() {
-                opts.sample2.to_string()
-            } else if let Some(sample_idx2) = opts.sample_idx2 {
-                match get_sample(&puzzle.text, sample_idx2) {
-                    Some(i) => i,
-                    None => {
-                        println!("no p2 input");
-                        return;
-                    }
+    run_inner(
+        year,
+        day,
+        |s: &str| s.to_string(),
+        false,
+        |s: &String| part1(s),
+        |s: &String| part2(s),
+        opts,
+    )
+}
+
+/// Like [`run`], but the input goes through `parse` once, timed on its own,
+/// and both parts get the result.
+pub fn run_parsed<P, T, F1, D1, F2, D2>(
+    year: Year,
+    day: Day,
+    parse: P,
+    part1: F1,
+    part2: F2,
+    opts: RunOptions,
+) -> Result<()>
+where
+    P: Fn(&str) -> T,
+    F1: Fn(&T) -> D1,
+    D1: Answer,
+    F2: Fn(&T) -> D2,
+    D2: Answer,
+{
+    run_inner(year, day, parse, true, part1, part2, opts)
+}
+
+/// What a run works on, resolved from the command line.
+struct RunContext {
+    cli: Cli,
+    p1: bool,
+    p2: bool,
+    /// None when running offline.
+    token: Option<Token>,
+    puzzle: Puzzle,
+    input: String,
+}
+
+impl RunContext {
+    fn resolve(year: Year, day: Day) -> Result<RunContext> {
+        let cli = Cli::parse();
+        if cli.submit && cli.p1 == cli.p2 {
+            bail!("--submit needs exactly one of --p1 or --p2");
+        }
+        // sample runs would mix their text into the records
+        if (cli.dev || cli.sample.is_some()) && cli.format != Format::Text {
+            bail!("--dev and --sample only work with --format text");
+        }
+        let mut p1 = cli.p1;
+        let mut p2 = cli.p2;
+        if !p1 && !p2 {
+            p1 = true;
+            p2 = true;
+        }
+        // an explicit input is all a plain run needs
+        let can_fall_back = cli.input_file.is_some() && cli.user.is_none() && !cli.submit;
+        let root = if cli.offline {
+            None
+        } else {
+            match setup_dir() {
+                Ok(root) => Some(root),
+                Err(e) if can_fall_back => {
+                    eprintln!("running offline: {e:#}");
+                    None
                 }
-            } else {
-                input_p1.to_string()
+                Err(e) => return Err(e),
+            }
+        };
+        let event = match &root {
+            Some(root) => Event::load(root, year)?,
+            None => Event::new(year, &Config::default())?,
+        };
+        event.check_day(day)?;
+        if !cli.p2 && !event.is_puzzle(day, Part::Two) {
+            p2 = false;
+        }
+
+        if cli.submit && cli.offline {
+            bail!("--submit doesn't work --offline");
+        }
+        let token = if root.is_none() {
+            None
+        } else {
+            let token = match &cli.user {
+                Some(user) => find_token(user),
+                None => current_token(),
             };
-            if p1 {
-                println!("{}", "input:".red().bold());
-                println!("{input_p1}");
-                println!("{}", "part 1:".red().bold());
-                println!("{}", part1(&input_p1));
-            }
-            if p2 {
-                if !p1 || input_p2 != input_p1 {
-                    println!("{}", "part 2 input:".red().bold());
-                    println!("{input_p2}");
+            match token {
+                Ok(token) => Some(token),
+                Err(e) if can_fall_back => {
+                    eprintln!("running offline: {e:#}");
+                    None
                 }
-                println!("{}", "part 2:".red().bold());
-                println!("{}", part2(&input_p2));
+                Err(e) => return Err(e.context("get token")),
             }
-        })();
+        };
+        let run = match &token {
+            Some(token) => Some(Run::new(token.clone(), year, day, Part::One)?),
+            None => None,
+        };
+        let cached = run.as_ref().map(|run| run.get_puzzle());
+        if let Some(Err(e)) = &cached {
+            eprintln!("no cached puzzle, run prepare first: {e}");
+        }
+        let puzzle = match cached {
+            Some(Ok(puzzle)) => puzzle,
+            _ => Puzzle {
+                title: "unknown".to_string(),
+                text: String::new(),
+            },
+        };
+        let input = match (&cli.input_file, &run) {
+            (None, Some(run)) => run.get_input().context("get input")?,
+            (path, _) => read_input(path.as_deref())?,
+        };
+        Ok(RunContext {
+            cli,
+            p1,
+            p2,
+            token,
+            puzzle,
+            input,
+        })
+    }
+}
+
+/// Part `n`'s answer once checked, ready to record and print.
+struct PartReport {
+    answer: String,
+    status: Status,
+    error: Option<String>,
+    /// The text format's line.
+    text: String,
+}
+
+fn run_inner<P, T, F1, D1, F2, D2>(
+    year: Year,
+    day: Day,
+    parse: P,
+    timed_parse: bool,
+    part1: F1,
+    part2: F2,
+    opts: RunOptions,
+) -> Result<()>
+where
+    P: Fn(&str) -> T,
+    F1: Fn(&T) -> D1,
+    D1: Answer,
+    F2: Fn(&T) -> D2,
+    D2: Answer,
+{
+    let ctx = RunContext::resolve(year, day)?;
+    let cli = &ctx.cli;
+    if cli.format == Format::Text {
+        println!("{}", ctx.puzzle.title.green().bold());
+    }
+    if cli.dev || cli.sample.is_some() {
+        run_dev_samples(&ctx, &opts, &|n, input| match n {
+            1 => part1(&parse(input)).into_answer(),
+            _ => part2(&parse(input)).into_answer(),
+        })?;
     }
-    type Boxed<'a> = Box<dyn Fn() -> String + 'a>;
+    let mut parse_micros = None;
+    let parsed = if timed_parse {
+        let (parsed, elapsed, time) = time_part(cli, &|| Ok(parse(&ctx.input)));
+        if cli.format == Format::Text {
+            print!("parse({time})");
+            if let Err((status, msg)) = &parsed {
+                print!(" {} {msg}", status.symbol());
+            }
+            print!("\t\t");
+        }
+        parse_micros = Some(elapsed.as_micros() as u64);
+        parsed
+    } else {
+        run_part(&|| Ok(parse(&ctx.input)))
+    };
+    type Boxed<'a, T> = Box<dyn Fn(&T) -> Result<String, String> + 'a>;
     for (n, f, b) in [
-        (1, Box::new(|| part1(&input).to_string()) as Boxed, p1),
-        (2, Box::new(|| part2(&input).to_string()) as Boxed, p2),
+        (
+            1,
+            Box::new(|t: &T| part1(t).into_answer()) as Boxed<T>,
+            ctx.p1,
+        ),
+        (
+            2,
+            Box::new(|t: &T| part2(t).into_answer()) as Boxed<T>,
+            ctx.p2,
+        ),
     ] {
         if b {
-            let start = Instant::now();
-            let mut res = f();
-            let time = start.elapsed().as_millis();
-            let mut ml = res.chars().any(|c| c == '\n');
-            if ml {
-                match crate::puzzle::map_get(&res)? {
-                    Some(s) => {
-                        res = s;
-                        ml = false;
+            let (outcome, elapsed, time) = match &parsed {
+                Ok(parsed) => time_part(cli, &|| f(parsed)),
+                Err((status, msg)) => (
+                    Err((*status, format!("parse {msg}"))),
+                    Duration::ZERO,
+                    "0ms".to_string(),
+                ),
+            };
+            let run = match &ctx.token {
+                Some(token) => Some(Run::new(token.clone(), year, day, Part::new(n)?)?),
+                None => None,
+            };
+            let report = classify_answer(cli, run.as_ref(), n, &time, outcome)?;
+            emit_result(&ctx, year, day, n, elapsed, parse_micros, report)?;
+        }
+        if cli.format == Format::Text {
+            print!("\t\t\t\t");
+        }
+    }
+    if cli.format == Format::Text {
+        println!();
+    }
+    Ok(())
+}
+
+/// Runs the selected parts on the puzzle's samples, or on the one given with
+/// `--sample`, failing if a declared sample answer doesn't match.
+fn run_dev_samples(
+    ctx: &RunContext,
+    opts: &RunOptions,
+    part: &dyn Fn(u16, &str) -> Result<String, String>,
+) -> Result<()> {
+    let puzzle = &ctx.puzzle;
+    let block =
+        ctx.cli
+            .sample
+            .map(|idx| {
+                let blocks = puzzle.samples();
+                blocks.get(idx).cloned().ok_or_else(|| {
+                    anyhow!("no sample block {idx}, the puzzle has {}", blocks.len())
+                })
+            })
+            .transpose()?;
+    if puzzle.text.is_empty() {
+        println!("no puzzle text, run prepare first");
+    } else if opts.no_sample {
+        println!("skipping sample");
+    }
+    let guesses = puzzle.sample_answers();
+    let mut last_input = None;
+    let mut checked = 0;
+    let mut failed = 0;
+    for (n, b) in [(1, ctx.p1), (2, ctx.p2)] {
+        if !b {
+            continue;
+        }
+        let guess = guesses.get(n as usize - 1).cloned().flatten();
+        let mut samples = match &block {
+            Some(block) => vec![Sample::new(block, None)],
+            None => dev_samples(opts, &puzzle.text, n),
+        };
+        // a guess for another block has no place next to --sample
+        if block.is_none() || guess.as_ref().map(|(input, _)| input) == block.as_ref() {
+            add_guess(&mut samples, guess);
+        }
+        if samples.is_empty() {
+            println!("no p{n} input");
+        }
+        for sample in samples {
+            if last_input.as_ref() != Some(&sample.input) {
+                let header = match last_input {
+                    None => "input:".to_string(),
+                    Some(_) => format!("part {n} input:"),
+                };
+                println!("{}", header.red().bold());
+                println!("{}", sample.input);
+            }
+            println!("{}", format!("part {n}:").red().bold());
+            let res = run_part(&|| part(n, &sample.input));
+            let guessed = if sample.guessed { " (guessed)" } else { "" };
+            match &sample.expected {
+                Some(expected) if res.as_ref() == Ok(expected) => {
+                    println!("{} {}{guessed}", describe(res), Status::Good.symbol());
+                }
+                Some(expected) => {
+                    // guesses can be wrong, so only declared answers fail the run
+                    if !sample.guessed {
+                        failed += 1;
                     }
-                    None => std::fs::write("/tmp/xaoc_multiline", res.as_bytes())?,
+                    println!(
+                        "{} {} expected {expected}{guessed}",
+                        describe(res),
+                        Status::Bad.symbol()
+                    );
                 }
+                None => println!("{}", describe(res)),
             }
-            let ml_s = if ml {
-                ", multiline output saved to /tmp/xaoc_multiline\n"
-            } else {
-                ""
-            };
-            let mut out = String::new();
-            out.push_str(&format!("part {n}({time}ms){ml_s}"));
-            if ml {
-                out.push_str(&format!("\n{res}\n"));
-            } else {
-                let run = Run::new(token.clone(), year, day, Part::new(n)?)?;
-                let sym = match run.check_answer(&res)? {
-                    AnswerStatus::Good => "✅",
-                    AnswerStatus::Bad => "❌",
-                    AnswerStatus::Unknown => "❓",
-                };
-                out.push_str(&format!(" {sym} [ {res} ]"));
+            if sample.expected.is_some() && !sample.guessed {
+                checked += 1;
+            }
+            last_input = Some(sample.input);
+        }
+    }
+    if failed > 0 {
+        bail!("{failed} of {checked} samples gave the wrong answer");
+    }
+    Ok(())
+}
+
+/// Runs `f` once, then benches it with `--bench` if it succeeded. Gives the
+/// time to record along with how to show it.
+fn time_part<T>(
+    cli: &Cli,
+    f: &dyn Fn() -> Result<T, String>,
+) -> (Result<T, (Status, String)>, Duration, String) {
+    let start = Instant::now();
+    let outcome = run_part(f);
+    let mut elapsed = start.elapsed();
+    let mut time = format!("{}ms", elapsed.as_millis());
+    if cli.bench && outcome.is_ok() {
+        let budget = Duration::from_secs_f64(cli.bench_budget);
+        let stats = bench(|| drop(f()), cli.bench_runs, budget);
+        elapsed = stats.median;
+        time = stats.to_string();
+    }
+    (outcome, elapsed, time)
+}
+
+/// Reads a drawn answer and checks the answer against what's known about it,
+/// submitting it with `--submit` if nothing is.
+fn classify_answer(
+    cli: &Cli,
+    run: Option<&Run>,
+    n: u16,
+    time: &str,
+    outcome: Result<String, (Status, String)>,
+) -> Result<PartReport> {
+    let (mut res, failure) = match outcome {
+        Ok(res) => (res, None),
+        Err(failure) => (String::new(), Some(failure)),
+    };
+    let mut ml = res.chars().any(|c| c == '\n');
+    if ml {
+        let letters = match run {
+            Some(_) => read_letters(&res)?,
+            None => crate::ocr::recognize(&res),
+        };
+        if let Some(s) = letters {
+            res = s;
+            ml = false;
+        }
+    }
+    let mut ml_s = String::new();
+    if let (true, Some(run)) = (ml, run) {
+        let path = run.set_multiline(&res)?;
+        ml_s = format!(", multiline output saved to {}\n", path.display());
+    }
+    let mut text = format!("part {n}({time}){ml_s}");
+    let mut error = None;
+    let status = if let Some((status, msg)) = failure {
+        text.push_str(&format!(" {} {msg}", status.symbol()));
+        error = Some(msg);
+        status
+    } else if ml {
+        text.push_str(&format!("\n{res}\n"));
+        Status::Multiline
+    } else if let Some(run) = run {
+        let (status, note) = match run.check_answer(&res)? {
+            AnswerStatus::Good => (Status::Good, String::new()),
+            AnswerStatus::Bad => (Status::Bad, String::new()),
+            AnswerStatus::OutOfBounds(bounds) => (Status::Bad, format!(" ({bounds})")),
+            AnswerStatus::Unknown if cli.submit => match run.submit(&res, false) {
+                Ok((verdict, _)) => {
+                    let status = match verdict {
+                        Verdict::Right => Status::Good,
+                        Verdict::Wrong { .. } => Status::Bad,
+                        _ => Status::Unknown,
+                    };
+                    (status, format!(" → {verdict}"))
+                }
+                Err(e) => {
+                    let note = format!(" → submit failed: {e}");
+                    error = Some(format!("submit failed: {e}"));
+                    (Status::Unknown, note)
+                }
+            },
+            AnswerStatus::Unknown => (Status::Unknown, String::new()),
+        };
+        text.push_str(&format!(" {} [ {res} ]{note}", status.symbol()));
+        status
+    } else {
+        text.push_str(&format!(" {} [ {res} ]", Status::Unknown.symbol()));
+        Status::Unknown
+    };
+    Ok(PartReport {
+        answer: res,
+        status,
+        error,
+        text,
+    })
+}
+
+/// Records part `n`'s timing in the history and prints its result.
+fn emit_result(
+    ctx: &RunContext,
+    year: Year,
+    day: Day,
+    n: u16,
+    elapsed: Duration,
+    parse_micros: Option<u64>,
+    report: PartReport,
+) -> Result<()> {
+    // timings on other inputs, or next to other runs, aren't comparable
+    let record = std::env::var_os(history::NO_HISTORY_VAR).is_none();
+    if let (None, Some(token), true) = (&ctx.cli.input_file, &ctx.token, record) {
+        let mut record = Record::new(year.0, day.0, n, token.id, elapsed, report.status);
+        record.parse_micros = parse_micros;
+        history::append(&setup_dir()?, &record)?;
+    }
+    if ctx.cli.format == Format::Text {
+        print!("{:<40}", report.text);
+        let _ = std::io::stdout().flush();
+    } else {
+        let result = PartResult {
+            year: year.0,
+            day: day.0,
+            part: n,
+            answer: report.answer,
+            status: report.status,
+            micros: elapsed.as_micros() as u64,
+            parse_micros,
+            error: report.error,
+        };
+        result.print(ctx.cli.format)?;
+    }
+    Ok(())
+}
+
+/// Checks part `n` from the tests generated by [`xaoc!`]: against its sample
+/// answers, then against the cached answer on the current user's cached
+/// input. Whatever isn't cached is skipped.
+pub fn check<F, D>(year: Year, day: Day, n: u16, part: F, opts: &RunOptions) -> Result<()>
+where
+    F: Fn(&str) -> D,
+    D: Answer,
+{
+    check_inner(
+        year,
+        day,
+        n,
+        |s: &str| s.to_string(),
+        |s: &String| part(s),
+        opts,
+    )
+}
+
+/// Like [`check`], for days using `parse`.
+pub fn check_parsed<P, T, F, D>(
+    year: Year,
+    day: Day,
+    n: u16,
+    parse: P,
+    part: F,
+    opts: &RunOptions,
+) -> Result<()>
+where
+    P: Fn(&str) -> T,
+    F: Fn(&T) -> D,
+    D: Answer,
+{
+    check_inner(year, day, n, parse, part, opts)
+}
+
+fn check_inner<P, T, F, D>(
+    year: Year,
+    day: Day,
+    n: u16,
+    parse: P,
+    part: F,
+    opts: &RunOptions,
+) -> Result<()>
+where
+    P: Fn(&str) -> T,
+    F: Fn(&T) -> D,
+    D: Answer,
+{
+    let run = match current_token() {
+        Ok(token) => Some(Run::new(token, year, day, Part::new(n)?)?),
+        Err(e) => {
+            println!("skipping cached answer: {e}");
+            None
+        }
+    };
+    check_run(year, day, n, run, parse, part, opts)
+}
+
+/// The checks of [`check_inner`], with the cache of `run` if any.
+fn check_run<P, T, F, D>(
+    year: Year,
+    day: Day,
+    n: u16,
+    run: Option<Run>,
+    parse: P,
+    part: F,
+    opts: &RunOptions,
+) -> Result<()>
+where
+    P: Fn(&str) -> T,
+    F: Fn(&T) -> D,
+    D: Answer,
+{
+    let answer = |input: &str| {
+        let parsed = run_part(&|| Ok(parse(input)))
+            .map_err(|(status, msg)| (status, format!("parse {msg}")))?;
+        let res = run_part(&|| part(&parsed).into_answer())?;
+        if !res.contains('\n') {
+            return Ok(res);
+        }
+        match read_letters(&res) {
+            Ok(Some(s)) => Ok(s),
+            _ => Ok(res),
+        }
+    };
+    let text = run
+        .as_ref()
+        .and_then(|run| run.get_puzzle().ok())
+        .map(|puzzle| puzzle.text)
+        .unwrap_or_default();
+    let mut failures = vec![];
+    for sample in dev_samples(opts, &text, n) {
+        let Some(expected) = sample.expected else {
+            continue;
+        };
+        let res = answer(&sample.input);
+        if res.as_ref() != Ok(&expected) {
+            failures.push(format!(
+                "sample {:?} gave {}, expected {expected}",
+                sample.input,
+                describe(res)
+            ));
+        }
+    }
+    if let Some(run) = run {
+        match (run.get_input(), run.get_answer()) {
+            (Ok(input), Ok(expected)) => {
+                let res = answer(&input);
+                if res.as_deref() != Ok(expected.as_str()) {
+                    failures.push(format!("input gave {}, expected {expected}", describe(res)));
+                }
+            }
+            _ => {
+                println!("skipping cached answer: no input or answer for {year} day {day} part {n}")
             }
-            print!("{out:<40}");
-            let _ = std::io::stdout().flush();
         }
-        print!("\t\t\t\t");
     }
-    println!();
+    if !failures.is_empty() {
+        bail!("part {n}:\n{}", failures.join("\n"));
+    }
     Ok(())
 }
 
-pub fn prepare(day: Day) -> Result<()> {
+pub fn prepare(day: Day, refresh: bool, wait: bool) -> Result<()> {
     let year = year()?;
     let token = current_token()?;
     let run = Run::new(token, year, day, Part::One)?;
     let code = PathBuf::from(format!("src/bin/{year}_{day}.rs"));
+    if wait {
+        _prepare_wait(&run, &code, &SystemClock)
+    } else {
+        _prepare(&run, &code, refresh)
+    }
+}
+
+/// How long to keep trying after the unlock, as the site can lag a little.
+const RETRY_WINDOW: Duration = Duration::from_secs(60);
+const RETRY_EVERY: Duration = Duration::from_secs(2);
+
+fn _prepare_wait(run: &Run, code: &Path, clock: &dyn Clock) -> Result<()> {
+    wait_until(run.unlock(), clock);
+    let deadline = clock.now() + RETRY_WINDOW;
+    loop {
+        match _prepare(run, code, false) {
+            Ok(()) => return Ok(()),
+            Err(e) if is_not_found(&e) && clock.now() < deadline => {
+                println!("not unlocked yet ({e}), retrying");
+                clock.sleep(RETRY_EVERY);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Whether the site answered 404, as it does for days still locked.
+fn is_not_found(e: &anyhow::Error) -> bool {
+    e.chain().any(|e| {
+        e.downcast_ref::<reqwest::Error>()
+            .and_then(|e| e.status())
+            .is_some_and(|status| status == StatusCode::NOT_FOUND)
+    })
+}
+
+fn _prepare(run: &Run, code: &Path, refresh: bool) -> Result<()> {
     if !code.exists() {
         let template = include_bytes!("../fixtures/template.rs");
-        std::fs::write(&code, template)?;
+        std::fs::write(code, template)?;
         println!("Created {:?}", code);
     } else {
         println!("Code already exists {:?}", code);
     }
-    let title = run.get_or_fetch_puzzle()?.title;
-    println!("{}", title.green().bold());
+    let puzzle = if refresh {
+        run.fetch_puzzle()?
+    } else {
+        run.get_or_fetch_puzzle()?
+    };
+    println!("{}", puzzle.title.green().bold());
+    println!("{} of 2 parts", puzzle.parts());
     let input = run.get_or_fetch_input()?;
     println!("input {} bytes", input.len());
     Ok(())
 }
 
-pub fn sync_answers(force: bool) -> Result<()> {
-    let year = year()?;
-    let token = current_token()?;
-    println!("syncing answers for year {year}");
-    for day in 1..=25 {
-        println!("day {day} ");
+/// Pause between page fetches, so a sweep over many days goes easy on the site.
+const SYNC_EVERY: Duration = Duration::from_secs(1);
+/// Days done by a sweep that stopped early, in the config dir.
+const SYNC_PROGRESS: &str = "sync_progress.json";
+
+/// Fetches the answers given on the site for the cwd's year or every started
+/// event, with the current token or all of them, and lists solved days that
+/// have no solution file.
+pub fn sync_answers(force: bool, all_years: bool, all_users: bool) -> Result<()> {
+    let root = setup_dir()?;
+    let base_url = base_url(&root)?;
+    let years = if all_years {
+        started_years(SystemTime::now())
+    } else {
+        vec![year()?]
+    };
+    let tokens = if all_users {
+        tokens()?
+    } else {
+        vec![current_token()?]
+    };
+    if tokens.is_empty() {
+        bail!("no tokens");
+    }
+    let mut syncer = Syncer::new(&root, &base_url, force, &SystemClock)?;
+    for token in &tokens {
+        for &year in &years {
+            syncer.year(token, year)?;
+        }
+    }
+    syncer.finish()?;
+    if let Ok(repo) = repo_root() {
+        let missing = missing_solutions(&root, &repo, &tokens, &years)?;
+        if !missing.is_empty() {
+            println!("solved on the site but no solution file:");
+            for (year, days) in &missing.into_iter().chunk_by(|&(year, _)| year) {
+                println!("  {year}: days {}", days.map(|(_, day)| day).join(", "));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Syncs answers a day at a time, keeping the days done in [`SYNC_PROGRESS`]
+/// so that a sweep stopped by an error resumes after the last day it finished.
+pub(crate) struct Syncer<'a> {
+    root: &'a Path,
+    base_url: &'a str,
+    force: bool,
+    clock: &'a dyn Clock,
+    last_fetch: Option<SystemTime>,
+    /// (user, year, day) done by this sweep or a stopped one.
+    done: BTreeSet<(u64, u16, u16)>,
+    /// (user, year) this sweep went through.
+    covered: BTreeSet<(u64, u16)>,
+}
+
+impl<'a> Syncer<'a> {
+    /// Picks up the progress of stopped sweeps.
+    pub(crate) fn new(
+        root: &'a Path,
+        base_url: &'a str,
+        force: bool,
+        clock: &'a dyn Clock,
+    ) -> Result<Self> {
+        let path = root.join(SYNC_PROGRESS);
+        let mut done = BTreeSet::new();
+        if path.exists() {
+            done = serde_json::from_reader(File::open(&path)?)
+                .with_context(|| path.display().to_string())?;
+        }
+        Ok(Syncer {
+            root,
+            base_url,
+            force,
+            clock,
+            last_fetch: None,
+            done,
+            covered: BTreeSet::new(),
+        })
+    }
+
+    /// Syncs the days of `year` not done yet, unless `force` starts it over.
+    pub(crate) fn year(&mut self, token: &Token, year: Year) -> Result<()> {
+        println!("syncing answers for year {year}, user {}", token.id);
+        let scope = (token.id, year.0);
+        if self.covered.insert(scope) && self.force {
+            self.done.retain(|&(user, y, _)| (user, y) != scope);
+        }
+        let resumed = self
+            .done
+            .iter()
+            .filter(|&&(user, y, _)| (user, y) == scope)
+            .count();
+        if resumed > 0 {
+            println!("resuming after {resumed} days already synced");
+        }
+        let event = Event::load(self.root, year)?;
+        for day in event.days() {
+            if event.unlock(day) > self.clock.now() {
+                println!("day {day} is still locked");
+                break;
+            }
+            if self.done.contains(&(token.id, year.0, day.0)) {
+                continue;
+            }
+            println!("day {day} ");
+            self.day(token, &event, day)
+                .with_context(|| format!("stopped at {year} day {day}, run again to resume"))?;
+            self.done.insert((token.id, year.0, day.0));
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        let path = self.root.join(SYNC_PROGRESS);
+        if self.done.is_empty() {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        } else {
+            std::fs::write(path, serde_json::to_string(&self.done)?)?;
+        }
+        Ok(())
+    }
+
+    fn day(&mut self, token: &Token, event: &Event, day: Day) -> Result<()> {
+        let year = Year(event.year);
         let mut answers = vec![];
-        for part in 1..=2 {
+        for (i, part) in [Part::One, Part::Two].into_iter().enumerate() {
+            if !event.is_puzzle(day, part) {
+                continue;
+            }
             print!("  part {part}: ");
-            let run = Run::new(token.clone(), year, Day(day), Part::new(part)?)?;
-            if run.get_answer().is_ok() && !force {
+            let run = Run::with_root(self.root, self.base_url, token.clone(), year, day, part)?;
+            if run.get_answer().is_ok() && !self.force {
                 println!("already exists, skipping");
                 continue;
             }
             if answers.is_empty() {
-                let client = client()?;
-                let page = client
-                    .get(format!("https://adventofcode.com/{year}/day/{day}"))
-                    .header(COOKIE, format!("session={}", token.token))
-                    .send()?
-                    .error_for_status()?
-                    .text()?;
+                let page = self.fetch(token, year, day)?;
+                // the page is at hand anyway, so keep the parts it shows, but
+                // the cache is shared by all users so only ever add parts
+                let cached = run.get_puzzle().map_or(0, |puzzle| puzzle.parts());
+                let fetched = Puzzle {
+                    title: String::new(),
+                    text: page.clone(),
+                };
+                if fetched.parts() > cached {
+                    run.set_puzzle(page.clone())?;
+                }
                 answers = ANSWER_RE
                     .captures_iter(&page)
                     .map(|cap| html_escape::decode_html_entities(&cap[1]).into_owned())
@@ -298,40 +1322,143 @@ pub fn sync_answers(force: bool) -> Result<()> {
                     break;
                 }
             }
-            let Some(answer) = answers.get(part as usize - 1) else {
+            let Some(answer) = answers.get(i) else {
                 println!("no answer");
                 break;
             };
             match run.check_answer(answer)? {
                 AnswerStatus::Good => println!("good"),
                 AnswerStatus::Bad => bail!("bad {answer}"),
+                AnswerStatus::OutOfBounds(bounds) => bail!("{answer} out of bounds {bounds}"),
                 AnswerStatus::Unknown => {
                     run.set_answer(answer)?;
                     println!("updated");
                 }
             }
         }
+        Ok(())
+    }
+
+    /// The puzzle page, at least [`SYNC_EVERY`] after the previous one.
+    fn fetch(&mut self, token: &Token, year: Year, day: Day) -> Result<String> {
+        if let Some(last) = self.last_fetch {
+            if let Ok(left) = (last + SYNC_EVERY).duration_since(self.clock.now()) {
+                self.clock.sleep(left);
+            }
+        }
+        self.last_fetch = Some(self.clock.now());
+        Ok(client()?
+            .get(url(self.base_url, &format!("/{year}/day/{day}"))?)
+            .header(COOKIE, format!("session={}", token.token))
+            .send()?
+            .error_for_status()?
+            .text()?)
+    }
+
+    /// Ends the sweep, so the next one over the same users and years starts
+    /// over. Stopped sweeps over others keep their progress.
+    pub(crate) fn finish(mut self) -> Result<()> {
+        let covered = std::mem::take(&mut self.covered);
+        self.done
+            .retain(|&(user, year, _)| !covered.contains(&(user, year)));
+        self.save()
     }
-    Ok(())
 }
 
-pub fn run_all(debug: bool) -> Result<()> {
-    let year = year()?;
-    let mut cmd = Command::new("cargo");
-    cmd.arg("build");
-    if !debug {
-        cmd.arg("--release");
+/// Days of `years` with an answer for any of `tokens` but no solution file.
+fn missing_solutions(
+    root: &Path,
+    repo: &Path,
+    tokens: &[Token],
+    years: &[Year],
+) -> Result<Vec<(u16, u16)>> {
+    let mut missing = vec![];
+    for &year in years {
+        for day in Event::load(root, year)?.days() {
+            if repo
+                .join(format!("aoc{year}/src/bin/{year}_{day}.rs"))
+                .exists()
+            {
+                continue;
+            }
+            for token in tokens {
+                let run = Run::cached(root, token.clone(), year, day, Part::One)?;
+                if run.get_answer().is_ok() {
+                    missing.push((year.0, day.0));
+                    break;
+                }
+            }
+        }
     }
-    if !cmd.status()?.success() {
-        bail!("build failed");
+    Ok(missing)
+}
+
+pub struct RunAllOptions {
+    pub debug: bool,
+    pub format: Format,
+    /// Every year with a crate if empty.
+    pub years: Vec<u16>,
+    pub day: Option<u16>,
+    pub part: Option<u16>,
+    /// Binaries to run at once.
+    pub jobs: usize,
+    pub limits: Limits,
+}
+
+/// Parses an inclusive range of years like `2015..2025` or a single year.
+pub fn parse_years(s: &str) -> Result<RangeInclusive<u16>> {
+    let (start, end) = match s.split_once("..") {
+        Some((start, end)) => (start, end.trim_start_matches('=')),
+        None => (s, s),
+    };
+    let range = start.parse()?..=end.parse()?;
+    if range.is_empty() {
+        bail!("empty year range {s}");
+    }
+    Ok(range)
+}
+
+/// The repository root, from either the root itself or one of the year crates.
+pub(crate) fn repo_root() -> Result<PathBuf> {
+    let cwd = std::env::current_dir()?;
+    if year().is_ok() {
+        return Ok(cwd.parent().unwrap().to_path_buf());
+    }
+    if cwd.join("xaoc").is_dir() {
+        return Ok(cwd);
     }
+    bail!("run me in aoc or aoc/aoc20??");
+}
 
-    let base = if debug {
-        Path::new("target/debug/")
+/// The years picked on the command line, or the one whose crate we're in,
+/// or every year with a crate.
+pub(crate) fn selected_years(root: &Path, years: &[u16]) -> Result<Vec<u16>> {
+    if !years.is_empty() {
+        Ok(years.to_vec())
+    } else if let Ok(year) = year() {
+        Ok(vec![year.0])
     } else {
-        Path::new("target/release/")
-    };
+        year_crates(root)
+    }
+}
+
+fn year_crates(root: &Path) -> Result<Vec<u16>> {
+    let mut years = vec![];
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        if let Some(cap) = AOC_YEAR_RE.captures(&entry.file_name().to_string_lossy()) {
+            years.push(cap[1].parse()?);
+        }
+    }
+    years.sort();
+    Ok(years)
+}
 
+/// Days of `year` that have a built binary in `base`.
+fn built_days(base: &Path, year: u16) -> Result<Vec<u16>> {
     let mut present = vec![];
     for entry in std::fs::read_dir(base)? {
         if let Ok(Some(n)) = (|| {
@@ -356,10 +1483,636 @@ pub fn run_all(debug: bool) -> Result<()> {
             present.push(n);
         }
     }
-
     present.sort();
-    for n in present {
-        Command::new(base.join(format!("{year}_{n}"))).status()?;
+    Ok(present)
+}
+
+#[derive(Default)]
+struct Summary {
+    counts: HashMap<Status, usize>,
+    micros: u64,
+    failed: Vec<(u16, u16, Outcome, String)>,
+}
+
+/// A built day binary picked by [`RunAllOptions`].
+struct DayBin {
+    year: u16,
+    day: u16,
+    /// The year crate, which the binary needs as its cwd.
+    dir: PathBuf,
+    exe: PathBuf,
+}
+
+impl DayBin {
+    /// Runs the selected parts with results as JSON lines.
+    fn command(&self, opts: &RunAllOptions) -> Command {
+        let mut cmd = Command::new(&self.exe);
+        cmd.current_dir(&self.dir).args(["--format", "json"]).env(
+            history::COMMIT_VAR,
+            history::git_commit().unwrap_or_default(),
+        );
+        if opts.jobs > 1 {
+            cmd.env(history::NO_HISTORY_VAR, "1");
+        }
+        if let Some(part) = opts.part {
+            cmd.arg(format!("--p{part}"));
+        }
+        cmd
+    }
+}
+
+/// Builds the selected years and lists the binaries of the selected days.
+fn day_bins(opts: &RunAllOptions) -> Result<Vec<DayBin>> {
+    let root = repo_root()?;
+    let years = selected_years(&root, &opts.years)?;
+    let mut bins = vec![];
+    for year in years {
+        let dir = root.join(format!("aoc{year}"));
+        if !dir.is_dir() {
+            eprintln!("no crate for {year}");
+            continue;
+        }
+        let mut cmd = Command::new("cargo");
+        cmd.arg("build").current_dir(&dir);
+        if !opts.debug {
+            cmd.arg("--release");
+        }
+        if let Some(day) = opts.day {
+            cmd.arg("--bin").arg(format!("{year}_{day}"));
+        }
+        // keep stdout for the results
+        cmd.stdout(Stdio::null());
+        if !cmd.status()?.success() {
+            bail!("build failed for {year}");
+        }
+
+        let base = dir.join(if opts.debug {
+            "target/debug"
+        } else {
+            "target/release"
+        });
+        let event = Event::load(&setup_dir()?, Year(year))?;
+        for day in built_days(&base, year)? {
+            if opts.day.is_some_and(|d| d != day) {
+                continue;
+            }
+            if let Err(e) = event.check_day(Day(day)) {
+                eprintln!("skipping {year}_{day}: {e}");
+                continue;
+            }
+            if let Some(part) = opts.part {
+                if !event.is_puzzle(Day(day), Part::new(part)?) {
+                    continue;
+                }
+            }
+            bins.push(DayBin {
+                year,
+                day,
+                dir: dir.clone(),
+                exe: base.join(format!("{year}_{day}")),
+            });
+        }
+    }
+    Ok(bins)
+}
+
+pub fn run_all(opts: &RunAllOptions) -> Result<()> {
+    let start = Instant::now();
+    let jobs = day_bins(opts)?
+        .into_iter()
+        .map(|bin| ((bin.year, bin.day), bin.command(opts)))
+        .collect();
+
+    let mut summary = Summary::default();
+    jobs::run(jobs, opts.jobs, &opts.limits, |finished| {
+        let (year, day) = finished.job;
+        for line in finished.stdout.lines() {
+            let Ok(result) = serde_json::from_str::<PartResult>(line) else {
+                // solutions printing their own stuff
+                if opts.format == Format::Text {
+                    println!("{line}");
+                }
+                continue;
+            };
+            result.print(opts.format)?;
+            *summary.counts.entry(result.status).or_default() += 1;
+            summary.micros += result.micros;
+        }
+        if !matches!(finished.outcome, Outcome::Ok) {
+            eprintln!(
+                "{}",
+                format!(
+                    "{year} day {day} {} after {:.3}s",
+                    finished.outcome,
+                    finished.elapsed.as_secs_f64()
+                )
+                .red()
+            );
+            summary
+                .failed
+                .push((year, day, finished.outcome, finished.stderr));
+        }
+        Ok(())
+    })?;
+
+    // stdout may be going to a script
+    summary
+        .failed
+        .sort_by_key(|(year, day, _, _)| (*year, *day));
+    for (year, day, outcome, stderr) in &summary.failed {
+        eprintln!("{}", format!("{year} day {day:>2}: {outcome}").red().bold());
+        // the error comes first, then possibly a long backtrace
+        for line in stderr.lines().take(5) {
+            eprintln!("    {line}");
+        }
+    }
+    let count = |status| summary.counts.get(&status).copied().unwrap_or(0);
+    let failed = |f: fn(&Outcome) -> bool| {
+        summary
+            .failed
+            .iter()
+            .filter(|(_, _, outcome, _)| f(outcome))
+            .count()
+    };
+    eprintln!(
+        "{} good, {} bad, {} unknown, {} multiline, {} errors, {} panics, {} failed, {} timed out, {} crashed in {:.3}s ({:.3}s in solutions)",
+        count(Status::Good),
+        count(Status::Bad),
+        count(Status::Unknown),
+        count(Status::Multiline),
+        count(Status::Error),
+        count(Status::Panic),
+        failed(|o| matches!(o, Outcome::Failed(_))),
+        failed(|o| matches!(o, Outcome::TimedOut)),
+        failed(|o| matches!(o, Outcome::Crashed(_))),
+        start.elapsed().as_secs_f64(),
+        summary.micros as f64 / 1e6,
+    );
+    Ok(())
+}
+
+/// Runs the selected days on the cached input of every account that has one
+/// and checks them against that account's answers, to catch solutions that
+/// only work on some inputs.
+pub fn verify(opts: &RunAllOptions) -> Result<()> {
+    let start = Instant::now();
+    let root = setup_dir()?;
+    let tokens = tokens()?;
+    let mut jobs = vec![];
+    for bin in day_bins(opts)? {
+        for token in &tokens {
+            let run = Run::cached(
+                &root,
+                token.clone(),
+                Year(bin.year),
+                Day(bin.day),
+                Part::One,
+            )?;
+            if !run.has_input()? {
+                continue;
+            }
+            let mut cmd = bin.command(opts);
+            cmd.args(["--user", &token.id.to_string()]);
+            jobs.push(((bin.year, bin.day, token.clone()), cmd));
+        }
+    }
+    let inputs = jobs.len();
+
+    let mut parts = BTreeMap::<_, Vec<(u64, Status)>>::new();
+    let mut failed = vec![];
+    jobs::run(jobs, opts.jobs, &opts.limits, |finished| {
+        let (year, day, token) = finished.job;
+        let user = token.id;
+        let mut results = finished
+            .stdout
+            .lines()
+            .filter_map(|line| serde_json::from_str::<PartResult>(line).ok())
+            .collect::<Vec<_>>();
+        for result in &mut results {
+            // runs only know wrong answers from submissions, but here the right one is known
+            let part = Part::new(result.part)?;
+            let run = Run::cached(&root, token.clone(), Year(year), Day(day), part)?;
+            if let (Status::Unknown, Ok(expected)) = (result.status, run.get_answer()) {
+                if result.answer != expected {
+                    result.status = Status::Bad;
+                }
+            }
+        }
+        let errors = results
+            .iter()
+            .filter_map(|r| r.error.as_ref().map(|e| format!(" {e}")))
+            .collect::<String>();
+        println!(
+            "{year} day {day:>2} user {user} {}{errors}",
+            results.iter().map(|r| r.status.symbol()).join(" ")
+        );
+        for result in results {
+            parts
+                .entry((year, day, result.part))
+                .or_default()
+                .push((user, result.status));
+        }
+        if !matches!(finished.outcome, Outcome::Ok) {
+            let msg = format!("{year} day {day} user {user} {}", finished.outcome);
+            eprintln!("{}", msg.red());
+            failed.push(msg);
+        }
+        Ok(())
+    })?;
+
+    let mut wrong = 0;
+    for ((year, day, part), results) in &parts {
+        // without an answer to compare with, a run says nothing
+        let bad = results
+            .iter()
+            .filter(|(_, status)| {
+                !matches!(status, Status::Good | Status::Unknown | Status::Multiline)
+            })
+            .map(|(user, _)| user)
+            .collect::<Vec<_>>();
+        if bad.is_empty() {
+            continue;
+        }
+        wrong += 1;
+        let good = results.iter().filter(|(_, s)| *s == Status::Good).count();
+        let msg = format!(
+            "{year} day {day:>2} part {part}: right on {good} of {} inputs, wrong for user {}",
+            results.len(),
+            bad.iter().join(", ")
+        );
+        eprintln!("{}", msg.red().bold());
+    }
+    failed.sort();
+    for msg in &failed {
+        eprintln!("{}", msg.red().bold());
+    }
+    eprintln!(
+        "{} parts on {inputs} inputs of {} users, {wrong} wrong, {} failed in {:.3}s",
+        parts.len(),
+        tokens.len(),
+        failed.len(),
+        start.elapsed().as_secs_f64()
+    );
+    if wrong > 0 || !failed.is_empty() {
+        bail!("some inputs gave wrong answers");
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{token, Site};
+    use assert_fs::fixture::{FileTouch, FileWriteStr, PathChild, PathCreateDir};
+    use assert_fs::TempDir;
+
+    #[test]
+    fn dev_samples() {
+        let text = "<pre><code>first\n</code></pre> <code>second</code>";
+        let opts = RunOptions {
+            sample_idx2: Some(1),
+            expected: "1",
+            samples: &[("extra", "2")],
+            ..Default::default()
+        };
+        assert_eq!(
+            super::dev_samples(&opts, text, 1),
+            [
+                Sample::new("first", Some("1")),
+                Sample::new("extra", Some("2"))
+            ]
+        );
+        assert_eq!(
+            super::dev_samples(&opts, text, 2),
+            [Sample::new("second", None)]
+        );
+        let opts = RunOptions {
+            no_sample: true,
+            ..opts
+        };
+        assert_eq!(super::dev_samples(&opts, text, 2), []);
+    }
+
+    #[test]
+    fn run_part() {
+        assert_eq!(super::run_part(&|| 42.into_answer()), Ok("42".to_string()));
+        let failed: Result<u32> = Err(anyhow!("inner")).context("outer");
+        let failed = failed.into_answer();
+        assert_eq!(failed, Err("outer: inner".to_string()));
+        assert_eq!(
+            super::run_part(&|| failed.clone()),
+            Err((Status::Error, "outer: inner".to_string()))
+        );
+        let line = line!() + 1;
+        let res = super::run_part(&|| -> Result<String, String> { panic!("oops {}", 1) });
+        let Err((Status::Panic, msg)) = res else {
+            panic!("expected a panic, got {res:?}");
+        };
+        assert!(msg.starts_with(&format!("panicked at {}:{line}:", file!())));
+        assert!(msg.ends_with(": oops 1"));
+        assert!(!CAPTURE.get());
+    }
+
+    #[test]
+    fn answer_types() {
+        let s = "x".to_string();
+        assert_eq!(1.5f64.into_answer(), Ok("1.5".to_string()));
+        assert_eq!(true.into_answer(), Ok("true".to_string()));
+        assert_eq!((&s).into_answer(), Ok("x".to_string()));
+        let boxed: Box<dyn Display> = Box::new(7);
+        assert_eq!(boxed.into_answer(), Ok("7".to_string()));
+        let ok: Result<&str, String> = Ok("y");
+        assert_eq!(ok.into_answer(), Ok("y".to_string()));
+    }
+
+    #[test]
+    fn add_guess() {
+        let guess = |input: &str, answer: &str| Some((input.to_string(), answer.to_string()));
+        let mut samples = vec![Sample::new("a", Some("1")), Sample::new("b", None)];
+        // declared answers win
+        super::add_guess(&mut samples, guess("a", "2"));
+        super::add_guess(&mut samples, guess("b", "3"));
+        super::add_guess(&mut samples, guess("c", "4"));
+        super::add_guess(&mut samples, None);
+        assert_eq!(samples[0], Sample::new("a", Some("1")));
+        assert_eq!(
+            samples[1..],
+            [
+                Sample {
+                    input: "b".into(),
+                    expected: Some("3".into()),
+                    guessed: true
+                },
+                Sample {
+                    input: "c".into(),
+                    expected: Some("4".into()),
+                    guessed: true
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_years() -> Result<()> {
+        assert_eq!(super::parse_years("2015..2025")?, 2015..=2025);
+        assert_eq!(super::parse_years("2015..=2016")?, 2015..=2016);
+        assert_eq!(super::parse_years("2017")?, 2017..=2017);
+        assert!(super::parse_years("2016..2015").is_err());
+        assert!(super::parse_years("2015..").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn year_crates() -> Result<()> {
+        let root = TempDir::new()?;
+        for dir in ["aoc2017", "aoc2015", "aoc", "xaoc", "aoc20xx"] {
+            root.child(dir).create_dir_all()?;
+        }
+        root.child("aoc2016").touch()?;
+        assert_eq!(super::year_crates(&root)?, [2015, 2017]);
+        // the tests don't run in a year crate
+        assert_eq!(selected_years(&root, &[])?, [2015, 2017]);
+        assert_eq!(selected_years(&root, &[2020, 2021])?, [2020, 2021]);
+        Ok(())
+    }
+
+    #[test]
+    fn check_parsed() -> Result<()> {
+        let root = TempDir::new()?;
+        root.child("user/123456/2015/1/input")
+            .write_str("1 2 3\n4")?;
+        let run = |n| Run::cached(&root, token(), Year(2015), Day(1), Part::new(n)?);
+        run(1)?.set_answer("10")?;
+        run(2)?.set_answer("24")?;
+        let parse = |s: &str| {
+            s.split_whitespace()
+                .map(|n| n.parse::<u64>().unwrap())
+                .collect::<Vec<_>>()
+        };
+        let sum = |v: &Vec<u64>| v.iter().sum::<u64>();
+        let product = |v: &Vec<u64>| v.iter().product::<u64>();
+        let opts = RunOptions {
+            sample: "5 5",
+            expected: "10",
+            expected2: "25",
+            ..Default::default()
+        };
+        check_run(Year(2015), Day(1), 1, Some(run(1)?), parse, sum, &opts)?;
+        check_run(Year(2015), Day(1), 2, Some(run(2)?), parse, product, &opts)?;
+        // the cached answer of part 1 isn't what part 2 gives
+        let err =
+            check_run(Year(2015), Day(1), 2, Some(run(1)?), parse, product, &opts).unwrap_err();
+        assert_eq!(err.to_string(), "part 2:\ninput gave 24, expected 10");
+
+        let parse = |s: &str| -> Vec<u64> { panic!("bad input {s:?}") };
+        for (n, part) in [(1, sum as fn(&Vec<u64>) -> u64), (2, product)] {
+            let err = check_run(Year(2015), Day(1), n, Some(run(n)?), parse, part, &opts)
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains("gave 💥 parse panicked at"), "{err}");
+            assert!(err.contains("bad input \"1 2 3\\n4\""), "{err}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn prepare() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let puzzle_mock = server
+            .mock("GET", "/2015/day/1")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1.html"))
+            .create();
+        let _m = server
+            .mock("GET", "/2015/day/1/input")
+            .with_status(200)
+            .with_body("(()(()(\n")
+            .create();
+        let run = site.run(1, Part::One)?;
+        let code = site.root.join("2015_1.rs");
+        _prepare(&run, &code, false)?;
+        assert!(code.exists());
+        assert_eq!(run.get_puzzle()?.title, "Day 1: Not Quite Lisp");
+        assert_eq!(run.get_input()?, "(()(()(");
+        puzzle_mock.remove();
+        let _m = server
+            .mock("GET", "/2015/day/1")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1_solved.html"))
+            .create();
+        _prepare(&run, &code, true)?;
+        assert_eq!(run.get_puzzle()?.parts(), 2);
+        Ok(())
+    }
+
+    struct FakeClock(std::cell::Cell<std::time::SystemTime>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> std::time::SystemTime {
+            self.0.get()
+        }
+
+        fn sleep(&self, d: Duration) {
+            self.0.set(self.0.get() + d);
+        }
+    }
+
+    #[test]
+    fn prepare_wait() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let locked_mock = server
+            .mock("GET", "/2015/day/1")
+            .with_status(404)
+            .expect(1)
+            .create();
+        let puzzle_mock = server
+            .mock("GET", "/2015/day/1")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1.html"))
+            .expect(1)
+            .create();
+        let _m = server
+            .mock("GET", "/2015/day/1/input")
+            .with_status(200)
+            .with_body("(()(()(\n")
+            .create();
+        let run = site.run(1, Part::One)?;
+        let clock = FakeClock((run.unlock() - Duration::from_secs(3)).into());
+        _prepare_wait(&run, &site.root.join("2015_1.rs"), &clock)?;
+        assert_eq!(clock.now(), run.unlock() + RETRY_EVERY);
+        assert_eq!(run.get_input()?, "(()(()(");
+        locked_mock.assert();
+        puzzle_mock.assert();
+
+        // gives up once the retry window is over
+        let _m = server.mock("GET", "/2015/day/2").with_status(404).create();
+        let run = site.run(2, Part::One)?;
+        let clock = FakeClock(run.unlock().into());
+        assert!(_prepare_wait(&run, &site.root.join("2015_2.rs"), &clock).is_err());
+        assert!(clock.now() >= run.unlock() + RETRY_WINDOW);
+
+        // and doesn't retry other errors
+        let _m = server.mock("GET", "/2015/day/3").with_status(500).create();
+        let run = site.run(3, Part::One)?;
+        let clock = FakeClock(run.unlock().into());
+        assert!(_prepare_wait(&run, &site.root.join("2015_3.rs"), &clock).is_err());
+        assert_eq!(clock.now(), run.unlock());
+        Ok(())
+    }
+
+    #[test]
+    fn sync_answers() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let _m = server
+            .mock("GET", "/2015/day/1")
+            .match_header("cookie", "session=F000")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1_solved.html"))
+            .create();
+        let _m = server
+            .mock(
+                "GET",
+                mockito::Matcher::Regex(r"^/2015/day/([2-9]|1\d|2\d)$".to_string()),
+            )
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_2_unsolved.html"))
+            .expect(24)
+            .create();
+        let clock = FakeClock(SystemTime::now().into());
+        let start = clock.now();
+        let mut syncer = Syncer::new(&site.root, &site.base_url, false, &clock)?;
+        syncer.year(&token(), Year(2015))?;
+        syncer.finish()?;
+        assert!(clock.now() >= start + SYNC_EVERY * 24);
+        assert!(!site.root.join(SYNC_PROGRESS).exists());
+        for (part, answer) in [(Part::One, "280"), (Part::Two, "1797")] {
+            let run = site.run(1, part)?;
+            assert_eq!(run.get_answer()?, answer);
+            assert_eq!(run.get_puzzle()?.parts(), 2);
+        }
+        let run = site.run(2, Part::One)?;
+        assert!(run.get_answer().is_err());
+
+        // a user who solved less doesn't take parts out of the cached puzzle
+        let other = Token::new(654321, "Pyotr", "F222", false);
+        let _m = server
+            .mock("GET", "/2015/day/1")
+            .match_header("cookie", "session=F222")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1.html"))
+            .create();
+        let mut syncer = Syncer::new(&site.root, &site.base_url, false, &clock)?;
+        syncer.year(&other, Year(2015))?;
+        syncer.finish()?;
+        let run = site.run_as(other, 1, Part::One)?;
+        assert!(run.get_answer().is_err());
+        assert_eq!(run.get_puzzle()?.parts(), 2);
+
+        // --force replaces a wrong cached answer
+        let run = site.run(1, Part::One)?;
+        run.set_answer("281")?;
+        let mut syncer = Syncer::new(&site.root, &site.base_url, true, &clock)?;
+        syncer.year(&token(), Year(2015))?;
+        syncer.finish()?;
+        assert_eq!(run.get_answer()?, "280");
+        Ok(())
+    }
+
+    #[test]
+    fn sync_resume() -> Result<()> {
+        let (site, mut server) = Site::new()?;
+        let repo = TempDir::new()?;
+        site.root
+            .child("config.toml")
+            .write_str("[days]\n2015 = 3")?;
+        let _m = server
+            .mock("GET", "/2015/day/1")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_2_unsolved.html"))
+            .expect(1)
+            .create();
+        let failing_mock = server
+            .mock("GET", "/2015/day/2")
+            .with_status(500)
+            .expect(1)
+            .create();
+        let _m = server
+            .mock("GET", "/2015/day/3")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1_solved.html"))
+            .create();
+        let clock = FakeClock(SystemTime::now().into());
+        // a stopped sweep over another user
+        site.root
+            .child(SYNC_PROGRESS)
+            .write_str("[[654321, 2016, 1]]")?;
+        let mut syncer = Syncer::new(&site.root, &site.base_url, false, &clock)?;
+        let err = syncer.year(&token(), Year(2015)).unwrap_err();
+        assert!(err.to_string().contains("2015 day 2"));
+        failing_mock.assert();
+        failing_mock.remove();
+        let _m = server
+            .mock("GET", "/2015/day/2")
+            .with_status(200)
+            .with_body(include_bytes!("../fixtures/puzzle_2015_1_solved.html"))
+            .create();
+
+        // day 1 isn't fetched again
+        let mut syncer = Syncer::new(&site.root, &site.base_url, false, &clock)?;
+        syncer.year(&token(), Year(2015))?;
+        syncer.finish()?;
+        assert_eq!(
+            std::fs::read_to_string(site.root.join(SYNC_PROGRESS))?,
+            "[[654321,2016,1]]"
+        );
+        let run = site.run(3, Part::One)?;
+        assert_eq!(run.get_answer()?, "280");
+
+        repo.child("aoc2015/src/bin/2015_2.rs").touch()?;
+        assert_eq!(
+            missing_solutions(&site.root, &repo, &[token()], &[Year(2015)])?,
+            [(2015, 3)]
+        );
+        Ok(())
+    }
+}
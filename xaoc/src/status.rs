@@ -0,0 +1,210 @@
+//! Stars, solutions and last runs of every day, from the local cache.
+
+use crate::auth::{current_token, Token};
+use crate::calendar::{Event, SystemClock};
+use crate::config::base_url;
+use crate::history::{self, Status};
+use crate::puzzle::Run;
+use crate::runner::{repo_root, selected_years, Syncer};
+use crate::{setup_dir, Part, Year};
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, PartialEq)]
+struct DayStatus {
+    stars: u16,
+    /// `src/bin/YYYY_D.rs` exists.
+    code: bool,
+    input: bool,
+    /// Good if the last run of every part was, else the first other status.
+    run: Option<Status>,
+}
+
+fn year_status(
+    root: &Path,
+    repo: &Path,
+    token: &Token,
+    event: &Event,
+    last: &HashMap<(u16, u16, u16), Status>,
+) -> Result<Vec<DayStatus>> {
+    let year = Year(event.year);
+    let mut days = vec![];
+    for day in event.days() {
+        let input = Run::cached(root, token.clone(), year, day, Part::One)?.has_input()?;
+        let mut stars = 0;
+        let mut run = None;
+        for (n, part) in [(1, Part::One), (2, Part::Two)] {
+            if !event.is_puzzle(day, part) {
+                continue;
+            }
+            let r = Run::cached(root, token.clone(), year, day, part)?;
+            stars += r.get_answer().is_ok() as u16;
+            if let Some(&status) = last.get(&(year.0, day.0, n)) {
+                if run.is_none() || run == Some(Status::Good) {
+                    run = Some(status);
+                }
+            }
+        }
+        days.push(DayStatus {
+            stars,
+            code: repo
+                .join(format!("aoc{year}/src/bin/{year}_{day}.rs"))
+                .exists(),
+            input,
+            run,
+        });
+    }
+    // the last star comes with all the others
+    let total = days.iter().map(|d| d.stars).sum::<u16>();
+    if total == event.days * 2 - 1 && days.last().is_some_and(|d| d.stars == 1) {
+        days.last_mut().unwrap().stars = 2;
+    }
+    Ok(days)
+}
+
+fn print_year(event: &Event, days: &[DayStatus]) {
+    let total = days.iter().map(|d| d.stars).sum::<u16>();
+    println!(
+        "{} {total}/{} stars",
+        event.year.to_string().green().bold(),
+        event.days * 2
+    );
+    let check = |b: bool| {
+        if b {
+            "  ✓".green()
+        } else {
+            "  ·".dimmed()
+        }
+    };
+    let rows = [
+        (
+            "day",
+            days.iter()
+                .zip(1..)
+                .map(|(_, n)| format!("{n:>3}").normal())
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "stars",
+            days.iter()
+                .map(|d| match d.stars {
+                    0 => "  ·".dimmed(),
+                    n => format!("{:>3}", "*".repeat(n as usize)).yellow().bold(),
+                })
+                .collect(),
+        ),
+        ("code", days.iter().map(|d| check(d.code)).collect()),
+        ("input", days.iter().map(|d| check(d.input)).collect()),
+        (
+            "run",
+            days.iter()
+                .map(|d| match d.run {
+                    None => "  ·".dimmed(),
+                    Some(Status::Good) => "  ✓".green(),
+                    Some(Status::Unknown | Status::Multiline) => "  ?".yellow(),
+                    Some(_) => "  ✗".red(),
+                })
+                .collect(),
+        ),
+    ];
+    for (label, cells) in rows {
+        let cells = cells.iter().map(|c| c.to_string()).collect::<String>();
+        println!("{label:<6}{cells}");
+    }
+}
+
+/// Prints a grid per year of the stars, solution files, cached inputs and
+/// last runs of each day, after fetching missing answers if `sync`.
+pub fn status(years: &[u16], sync: bool) -> Result<()> {
+    let repo = repo_root()?;
+    let root = setup_dir()?;
+    let token = current_token()?;
+    let mut last = HashMap::new();
+    for record in history::load(&root)?
+        .into_iter()
+        .filter(|r| r.user == token.id)
+    {
+        last.insert((record.year, record.day, record.part), record.status);
+    }
+    let base_url = base_url(&root)?;
+    let mut syncer = if sync {
+        Some(Syncer::new(&root, &base_url, false, &SystemClock)?)
+    } else {
+        None
+    };
+    for (i, year) in selected_years(&repo, years)?.into_iter().enumerate() {
+        if let Some(syncer) = &mut syncer {
+            syncer.year(&token, Year(year))?;
+        }
+        if i > 0 {
+            println!();
+        }
+        let event = Event::load(&root, Year(year))?;
+        print_year(&event, &year_status(&root, &repo, &token, &event, &last)?);
+    }
+    if let Some(syncer) = syncer {
+        syncer.finish()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::token;
+    use assert_fs::fixture::{FileTouch, FileWriteStr, PathChild};
+    use assert_fs::TempDir;
+
+    #[test]
+    fn year_status() -> Result<()> {
+        let root = TempDir::new()?;
+        let repo = TempDir::new()?;
+        let config: Config = toml::from_str("[days]\n2015 = 3")?;
+        let event = Event::new(Year(2015), &config)?;
+        for day in 1..=3 {
+            root.child(format!("user/123456/2015/{day}/1/answer"))
+                .write_str("1")?;
+            if day < 3 {
+                root.child(format!("user/123456/2015/{day}/2/answer"))
+                    .write_str("2")?;
+            }
+        }
+        root.child("user/123456/2015/2/input").write_str("x")?;
+        repo.child("aoc2015/src/bin/2015_2.rs").touch()?;
+        let last = HashMap::from([
+            ((2015, 1, 1), Status::Good),
+            ((2015, 1, 2), Status::Bad),
+            ((2015, 2, 1), Status::Good),
+            ((2015, 2, 2), Status::Good),
+        ]);
+        let days = super::year_status(&root, &repo, &token(), &event, &last)?;
+        assert_eq!(
+            days,
+            [
+                DayStatus {
+                    stars: 2,
+                    code: false,
+                    input: false,
+                    run: Some(Status::Bad)
+                },
+                DayStatus {
+                    stars: 2,
+                    code: true,
+                    input: true,
+                    run: Some(Status::Good)
+                },
+                // part 2 of the last day is free
+                DayStatus {
+                    stars: 2,
+                    code: false,
+                    input: false,
+                    run: None
+                },
+            ]
+        );
+        Ok(())
+    }
+}
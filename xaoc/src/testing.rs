@@ -0,0 +1,46 @@
+//! Setup shared by the tests.
+
+use crate::auth::Token;
+use crate::puzzle::Run;
+use crate::{Day, Part, Year};
+use anyhow::Result;
+use assert_fs::TempDir;
+use mockito::ServerGuard;
+
+pub(crate) fn token() -> Token {
+    Token::new(123456, "Vasily", "F000", true)
+}
+
+/// A temporary config dir for runs in 2015 against a mock of the site.
+pub(crate) struct Site {
+    pub(crate) root: TempDir,
+    pub(crate) base_url: String,
+}
+
+impl Site {
+    /// The site along with its server, to set up the mocks on.
+    pub(crate) fn new() -> Result<(Site, ServerGuard)> {
+        let server = mockito::Server::new();
+        let site = Site {
+            root: TempDir::new()?,
+            base_url: server.url(),
+        };
+        Ok((site, server))
+    }
+
+    /// A run of [`token`] on the site.
+    pub(crate) fn run(&self, day: u16, part: Part) -> Result<Run> {
+        self.run_as(token(), day, part)
+    }
+
+    pub(crate) fn run_as(&self, token: Token, day: u16, part: Part) -> Result<Run> {
+        Run::with_root(
+            &self.root,
+            &self.base_url,
+            token,
+            Year(2015),
+            Day(day),
+            part,
+        )
+    }
+}